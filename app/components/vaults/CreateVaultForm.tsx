@@ -7,6 +7,7 @@ import { Plus, Loader2, CheckCircle, XCircle } from 'lucide-react';
 import { useChronosVault } from '@/src/hooks/useChronosPrograms';
 import { useWalletReady } from '@/src/hooks/useProgram';
 import { getExplorerUrl } from '@/src/config/programs';
+import { TOKEN_CONFIG } from '@/src/config/token';
 
 type StrategyType = 'YieldOptimization' | 'DeltaNeutral' | 'Arbitrage';
 
@@ -61,7 +62,8 @@ export function CreateVaultForm({ selectedStrategy, onSuccess }: CreateVaultForm
       const result = await initializeVault(
         selectedStrategy,
         riskLevel,
-        rebalanceFrequency
+        rebalanceFrequency,
+        TOKEN_CONFIG.mint
       );
 
       setTxSignature(result.signature);
@@ -2,7 +2,7 @@ import { useConnection, useWallet, useAnchorWallet } from '@solana/wallet-adapte
 import { PublicKey, Transaction, SystemProgram, TransactionInstruction } from '@solana/web3.js';
 import { PROGRAM_IDS, NETWORK_CONFIG } from '../config/programs';
 import { useState } from 'react';
-import { AnchorProvider } from '@coral-xyz/anchor';
+import { AnchorProvider, utils } from '@coral-xyz/anchor';
 
 // Simple cache to prevent repeated RPC calls
 const vaultCache = new Map<string, { exists: boolean; vaultAddress?: PublicKey; timestamp: number }>();
@@ -15,6 +15,24 @@ const getInstructionDiscriminator = (name: string): Buffer => {
   return hash.slice(0, 8);
 };
 
+// Helper to create account discriminator (first 8 bytes of sha256 hash of "account:AccountName")
+const getAccountDiscriminator = (name: string): Buffer => {
+  const crypto = require('crypto');
+  const hash = crypto.createHash('sha256').update(`account:${name}`).digest();
+  return hash.slice(0, 8);
+};
+
+const TOKEN_PROGRAM_ID = new PublicKey('TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA');
+const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey('ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL');
+
+// Vault PDAs are seeded by creator and a per-creator vault id; the app manages the first vault
+const DEFAULT_VAULT_ID = 0;
+const vaultIdSeed = (vaultId: number): Buffer => {
+  const seed = Buffer.alloc(8);
+  seed.writeBigUInt64LE(BigInt(vaultId));
+  return seed;
+};
+
 /**
  * Hook for interacting with CHRONOS Vault program
  */
@@ -64,7 +82,7 @@ export function useChronosVault() {
     try {
       const programId = PROGRAM_IDS.CHRONOS_VAULT;
       const [vaultPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from('vault'), wallet.publicKey.toBuffer()],
+        [Buffer.from('vault'), wallet.publicKey.toBuffer(), vaultIdSeed(DEFAULT_VAULT_ID)],
         programId
       );
 
@@ -89,12 +107,13 @@ export function useChronosVault() {
   };
 
   /**
-   * Initialize a new vault
+   * Initialize a new vault holding `assetMint`
    */
   const initializeVault = async (
     strategyType: 'YieldOptimization' | 'DeltaNeutral' | 'Arbitrage',
     riskLevel: number,
-    rebalanceFrequency: number
+    rebalanceFrequency: number,
+    assetMint: PublicKey
   ) => {
     if (!anchorWallet || !wallet.publicKey) {
       throw new Error('Wallet not connected');
@@ -105,7 +124,7 @@ export function useChronosVault() {
       setError(null);
 
       console.log('=== Starting vault initialization ===');
-      console.log('Params:', { strategyType, riskLevel, rebalanceFrequency });
+      console.log('Params:', { strategyType, riskLevel, rebalanceFrequency, assetMint: assetMint.toBase58() });
 
       // Check if vault already exists
       const { exists, vaultAddress } = await checkVaultExists();
@@ -119,24 +138,53 @@ export function useChronosVault() {
       // Derive vault PDA
       const programId = PROGRAM_IDS.CHRONOS_VAULT;
       const [vaultPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from('vault'), wallet.publicKey.toBuffer()],
+        [Buffer.from('vault'), wallet.publicKey.toBuffer(), vaultIdSeed(DEFAULT_VAULT_ID)],
         programId
       );
       console.log('Vault PDA:', vaultPda.toBase58());
 
+      // The registry entry is seeded by the registry's current vault count, read after the
+      // 8 byte discriminator
+      const [registryPda] = PublicKey.findProgramAddressSync([Buffer.from('vault_registry')], programId);
+      const registryInfo = await connection.getAccountInfo(registryPda);
+      if (!registryInfo) {
+        throw new Error('Vault registry is not initialized');
+      }
+      const vaultCount = registryInfo.data.readBigUInt64LE(8);
+      const registryEntrySeed = Buffer.alloc(8);
+      registryEntrySeed.writeBigUInt64LE(vaultCount);
+      const [registryEntryPda] = PublicKey.findProgramAddressSync(
+        [Buffer.from('registry_entry'), registryEntrySeed],
+        programId
+      );
+
+      const [vaultTokenAccount] = PublicKey.findProgramAddressSync(
+        [Buffer.from('vault_tokens'), vaultPda.toBuffer()],
+        programId
+      );
+      const [shareMint] = PublicKey.findProgramAddressSync(
+        [Buffer.from('share_mint'), vaultPda.toBuffer()],
+        programId
+      );
+      const [history] = PublicKey.findProgramAddressSync(
+        [Buffer.from('history'), vaultPda.toBuffer()],
+        programId
+      );
+
       // Build instruction data manually
-      // Format: [discriminator (8 bytes)] [strategy_type (1 byte)] [risk_level (1 byte)] [rebalance_frequency (8 bytes)]
+      // Format: [discriminator (8 bytes)] [vault_id (8 bytes)] [strategy_type (1 byte)] [risk_level (1 byte)] [rebalance_frequency (8 bytes)]
       const discriminator = getInstructionDiscriminator('initialize_vault');
 
       // Map strategy type to enum index
       const strategyIndex = strategyType === 'YieldOptimization' ? 0 : strategyType === 'DeltaNeutral' ? 1 : 2;
 
       // Create instruction data buffer
-      const data = Buffer.alloc(18); // 8 + 1 + 1 + 8
+      const data = Buffer.alloc(26); // 8 + 8 + 1 + 1 + 8
       discriminator.copy(data, 0);
-      data.writeUInt8(strategyIndex, 8); // strategy_type enum
-      data.writeUInt8(riskLevel, 9); // risk_level
-      data.writeBigInt64LE(BigInt(rebalanceFrequency), 10); // rebalance_frequency
+      data.writeBigUInt64LE(BigInt(DEFAULT_VAULT_ID), 8); // vault_id
+      data.writeUInt8(strategyIndex, 16); // strategy_type enum
+      data.writeUInt8(riskLevel, 17); // risk_level
+      data.writeBigInt64LE(BigInt(rebalanceFrequency), 18); // rebalance_frequency
 
       console.log('Instruction data:', data.toString('hex'));
 
@@ -144,7 +192,14 @@ export function useChronosVault() {
       const instruction = new TransactionInstruction({
         keys: [
           { pubkey: vaultPda, isSigner: false, isWritable: true },
+          { pubkey: registryPda, isSigner: false, isWritable: true },
+          { pubkey: registryEntryPda, isSigner: false, isWritable: true },
+          { pubkey: assetMint, isSigner: false, isWritable: false },
+          { pubkey: vaultTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: shareMint, isSigner: false, isWritable: true },
+          { pubkey: history, isSigner: false, isWritable: true },
           { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
+          { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
           { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         ],
         programId,
@@ -184,9 +239,57 @@ export function useChronosVault() {
   };
 
   /**
-   * Deposit into vault
+   * Accounts shared by the deposit and withdraw instructions of the connected wallet's vault
+   */
+  const vaultTransferAccounts = (tokenMint: PublicKey, user: PublicKey) => {
+    const programId = PROGRAM_IDS.CHRONOS_VAULT;
+
+    const [vaultPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('vault'), user.toBuffer(), vaultIdSeed(DEFAULT_VAULT_ID)],
+      programId
+    );
+    const [userPositionPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('position'), vaultPda.toBuffer(), user.toBuffer()],
+      programId
+    );
+    const [vaultTokenAccount] = PublicKey.findProgramAddressSync(
+      [Buffer.from('vault_tokens'), vaultPda.toBuffer()],
+      programId
+    );
+    const [shareMint] = PublicKey.findProgramAddressSync(
+      [Buffer.from('share_mint'), vaultPda.toBuffer()],
+      programId
+    );
+    const [history] = PublicKey.findProgramAddressSync(
+      [Buffer.from('history'), vaultPda.toBuffer()],
+      programId
+    );
+
+    // The user's asset and share token accounts are their ATAs
+    const [userTokenAccount] = PublicKey.findProgramAddressSync(
+      [user.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), tokenMint.toBuffer()],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+    const [userShareAccount] = PublicKey.findProgramAddressSync(
+      [user.toBuffer(), TOKEN_PROGRAM_ID.toBuffer(), shareMint.toBuffer()],
+      ASSOCIATED_TOKEN_PROGRAM_ID
+    );
+
+    return {
+      vaultPda,
+      userPositionPda,
+      userTokenAccount,
+      vaultTokenAccount,
+      shareMint,
+      history,
+      userShareAccount,
+    };
+  };
+
+  /**
+   * Deposit into vault, reverting if fewer than `minSharesOut` shares are minted
    */
-  const deposit = async (amount: number, tokenMint: PublicKey) => {
+  const deposit = async (amount: number, tokenMint: PublicKey, minSharesOut: number = 0) => {
     if (!anchorWallet || !wallet.publicKey) {
       throw new Error('Wallet not connected');
     }
@@ -196,58 +299,29 @@ export function useChronosVault() {
       setError(null);
 
       const programId = PROGRAM_IDS.CHRONOS_VAULT;
-
-      // Derive vault PDA
-      const [vaultPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from('vault'), wallet.publicKey.toBuffer()],
-        programId
-      );
-
-      // Derive user position PDA
-      const [userPositionPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from('position'), vaultPda.toBuffer(), wallet.publicKey.toBuffer()],
-        programId
-      );
-
-      // Token program and ATA program
-      const TOKEN_PROGRAM_ID = new PublicKey('TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA');
-      const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey('ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL');
-
-      // Derive vault token account (ATA)
-      const [vaultTokenAccount] = PublicKey.findProgramAddressSync(
-        [
-          vaultPda.toBuffer(),
-          TOKEN_PROGRAM_ID.toBuffer(),
-          tokenMint.toBuffer(),
-        ],
-        ASSOCIATED_TOKEN_PROGRAM_ID
-      );
-
-      // Get user's token account (ATA)
-      const [userTokenAccount] = PublicKey.findProgramAddressSync(
-        [
-          wallet.publicKey.toBuffer(),
-          TOKEN_PROGRAM_ID.toBuffer(),
-          tokenMint.toBuffer(),
-        ],
-        ASSOCIATED_TOKEN_PROGRAM_ID
-      );
+      const accounts = vaultTransferAccounts(tokenMint, wallet.publicKey);
 
       // Build instruction data
       const discriminator = getInstructionDiscriminator('deposit');
-      const data = Buffer.alloc(8 + 8); // discriminator + amount
+      const data = Buffer.alloc(8 + 8 + 8); // discriminator + amount + min_shares_out
       discriminator.copy(data, 0);
       data.writeBigUInt64LE(BigInt(Math.floor(amount * 1e6)), 8); // Amount with 6 decimals
+      data.writeBigUInt64LE(BigInt(minSharesOut), 16);
 
-      // Create instruction
+      // Create instruction; the optional allowlist entry is omitted by passing the program id
       const instruction = new TransactionInstruction({
         keys: [
-          { pubkey: vaultPda, isSigner: false, isWritable: true },
-          { pubkey: userPositionPda, isSigner: false, isWritable: true },
+          { pubkey: accounts.vaultPda, isSigner: false, isWritable: true },
+          { pubkey: accounts.userPositionPda, isSigner: false, isWritable: true },
           { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
-          { pubkey: userTokenAccount, isSigner: false, isWritable: true },
-          { pubkey: vaultTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: accounts.userTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: accounts.vaultTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: accounts.shareMint, isSigner: false, isWritable: true },
+          { pubkey: accounts.history, isSigner: false, isWritable: true },
+          { pubkey: accounts.userShareAccount, isSigner: false, isWritable: true },
+          { pubkey: programId, isSigner: false, isWritable: false },
           { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+          { pubkey: ASSOCIATED_TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
           { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         ],
         programId,
@@ -271,9 +345,9 @@ export function useChronosVault() {
   };
 
   /**
-   * Withdraw from vault
+   * Withdraw from vault, reverting if fewer than `minAssetsOut` tokens are returned
    */
-  const withdraw = async (shares: number, tokenMint: PublicKey) => {
+  const withdraw = async (shares: number, tokenMint: PublicKey, minAssetsOut: number = 0) => {
     if (!anchorWallet || !wallet.publicKey) {
       throw new Error('Wallet not connected');
     }
@@ -283,57 +357,26 @@ export function useChronosVault() {
       setError(null);
 
       const programId = PROGRAM_IDS.CHRONOS_VAULT;
-
-      // Derive vault PDA
-      const [vaultPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from('vault'), wallet.publicKey.toBuffer()],
-        programId
-      );
-
-      // Derive user position PDA
-      const [userPositionPda] = PublicKey.findProgramAddressSync(
-        [Buffer.from('position'), vaultPda.toBuffer(), wallet.publicKey.toBuffer()],
-        programId
-      );
-
-      // Token program and ATA program
-      const TOKEN_PROGRAM_ID = new PublicKey('TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA');
-      const ASSOCIATED_TOKEN_PROGRAM_ID = new PublicKey('ATokenGPvbdGVxr1b2hvZbsiqW5xWH25efTNsLJA8knL');
-
-      // Derive vault token account (ATA)
-      const [vaultTokenAccount] = PublicKey.findProgramAddressSync(
-        [
-          vaultPda.toBuffer(),
-          TOKEN_PROGRAM_ID.toBuffer(),
-          tokenMint.toBuffer(),
-        ],
-        ASSOCIATED_TOKEN_PROGRAM_ID
-      );
-
-      // Get user's token account (ATA)
-      const [userTokenAccount] = PublicKey.findProgramAddressSync(
-        [
-          wallet.publicKey.toBuffer(),
-          TOKEN_PROGRAM_ID.toBuffer(),
-          tokenMint.toBuffer(),
-        ],
-        ASSOCIATED_TOKEN_PROGRAM_ID
-      );
+      const accounts = vaultTransferAccounts(tokenMint, wallet.publicKey);
 
       // Build instruction data
       const discriminator = getInstructionDiscriminator('withdraw');
-      const data = Buffer.alloc(8 + 8); // discriminator + shares
+      const data = Buffer.alloc(8 + 8 + 8); // discriminator + shares + min_assets_out
       discriminator.copy(data, 0);
       data.writeBigUInt64LE(BigInt(Math.floor(shares * 1e6)), 8); // Shares with 6 decimals
+      data.writeBigUInt64LE(BigInt(minAssetsOut), 16);
 
       // Create instruction
       const instruction = new TransactionInstruction({
         keys: [
-          { pubkey: vaultPda, isSigner: false, isWritable: true },
-          { pubkey: userPositionPda, isSigner: false, isWritable: true },
+          { pubkey: accounts.vaultPda, isSigner: false, isWritable: true },
+          { pubkey: accounts.userPositionPda, isSigner: false, isWritable: true },
           { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
-          { pubkey: userTokenAccount, isSigner: false, isWritable: true },
-          { pubkey: vaultTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: accounts.userTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: accounts.vaultTokenAccount, isSigner: false, isWritable: true },
+          { pubkey: accounts.shareMint, isSigner: false, isWritable: true },
+          { pubkey: accounts.history, isSigner: false, isWritable: true },
+          { pubkey: accounts.userShareAccount, isSigner: false, isWritable: true },
           { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
           { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         ],
@@ -367,30 +410,38 @@ export function useChronosVault() {
       // Get all vault accounts
       const accounts = await connection.getProgramAccounts(programId, {
         filters: [
-          { dataSize: 200 } // Approximate vault account size
+          { memcmp: { offset: 0, bytes: utils.bytes.bs58.encode(getAccountDiscriminator('Vault')) } }
         ]
       });
 
       let totalValueLocked = 0;
       let activeVaults = accounts.length;
-      let totalDeposits = 0;
 
       for (const { account } of accounts) {
         try {
           const data = account.data;
-          if (data.length < 100) continue;
 
-          // Read total_deposits (at offset 41)
-          const deposits = data.readBigUInt64LE(41);
-          totalDeposits += Number(deposits);
+          // Skip discriminator, creator, vault_id, registry_index and authority, then the
+          // optional pending_authority, asset_mint and vault_token_account
+          let offset = 8 + 32 + 8 + 8 + 32;
+          offset += data.readUInt8(offset) === 1 ? 33 : 1;
+          offset += 32;
+          const vaultTokenAccount = new PublicKey(data.subarray(offset, offset + 32));
+
+          // Skip vault_token_account, share_mint, strategy_type, risk_level and
+          // rebalance_frequency to reach deployed_assets
+          offset += 32 + 32 + 1 + 1 + 8;
+          const deployedAssets = data.readBigUInt64LE(offset);
+
+          // TVL counts the liquid balance and assets deployed to strategies
+          const balance = await connection.getTokenAccountBalance(vaultTokenAccount);
+          const assets = BigInt(balance.value.amount) + deployedAssets;
+          totalValueLocked += Number(assets) / 10 ** balance.value.decimals;
         } catch (err) {
           console.error('Error parsing vault:', err);
         }
       }
 
-      // Convert from lamports to SOL (assuming deposits are in lamports)
-      totalValueLocked = totalDeposits / 1e9;
-
       return {
         totalValueLocked,
         activeVaults,
@@ -599,6 +650,7 @@ export function useChronosDex() {
         keys: [
           { pubkey: marketAddress, isSigner: false, isWritable: false },
           { pubkey: orderPda, isSigner: false, isWritable: true },
+          { pubkey: wallet.publicKey, isSigner: true, isWritable: false },
           { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
           { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         ],
@@ -1396,6 +1448,7 @@ export function useChronosOrchestrator() {
         keys: [
           { pubkey: orchestratorPda, isSigner: false, isWritable: true },
           { pubkey: reservationPda, isSigner: false, isWritable: true },
+          { pubkey: wallet.publicKey, isSigner: true, isWritable: false },
           { pubkey: wallet.publicKey, isSigner: true, isWritable: true },
           { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
         ],
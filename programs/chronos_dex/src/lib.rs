@@ -20,6 +20,8 @@ pub mod chronos_dex {
         market.quote_mint = quote_mint;
         market.current_batch_id = 0;
         market.total_volume = 0;
+        market.last_clearing_price = 0;
+        market.last_clearing_at = 0;
         market.bump = ctx.bumps.market;
         
         msg!("DEX Market initialized for {}/{}", base_mint, quote_mint);
@@ -99,14 +101,37 @@ pub mod chronos_dex {
         Ok(())
     }
 
-    /// Settle a matched trade
+    /// Settle a matched buy and sell order at the batch clearing price: the buyer pays
+    /// quote tokens, the seller delivers base tokens and both orders record the fill
     pub fn settle_trade(
         ctx: Context<SettleTrade>,
         amount: u64,
         price: u64,
     ) -> Result<()> {
-        let market = &ctx.accounts.market;
-        
+        let market = &mut ctx.accounts.market;
+        let buy_order = &mut ctx.accounts.buy_order;
+        let sell_order = &mut ctx.accounts.sell_order;
+
+        require!(
+            buy_order.market == market.key() && sell_order.market == market.key(),
+            DexError::OrderMismatch
+        );
+        require!(
+            buy_order.side == OrderSide::Buy && sell_order.side == OrderSide::Sell,
+            DexError::OrderMismatch
+        );
+        require!(
+            buy_order.trader == ctx.accounts.buyer.key()
+                && sell_order.trader == ctx.accounts.seller.key(),
+            DexError::Unauthorized
+        );
+        require!(
+            sell_order.price <= price && price <= buy_order.price,
+            DexError::PriceNotCrossed
+        );
+        buy_order.fill(amount)?;
+        sell_order.fill(amount)?;
+
         // Calculate quote amount
         let quote_amount = (amount as u128)
             .checked_mul(price as u128)
@@ -114,24 +139,28 @@ pub mod chronos_dex {
             .checked_div(1_000_000) // Price precision
             .unwrap() as u64;
 
-        // Transfer base tokens from buyer to seller
-        let cpi_accounts_base = Transfer {
-            from: ctx.accounts.buyer_base_account.to_account_info(),
-            to: ctx.accounts.seller_base_account.to_account_info(),
+        // Transfer quote tokens from buyer to seller
+        let cpi_accounts_quote = Transfer {
+            from: ctx.accounts.buyer_quote_account.to_account_info(),
+            to: ctx.accounts.seller_quote_account.to_account_info(),
             authority: ctx.accounts.buyer.to_account_info(),
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx_base = CpiContext::new(cpi_program.clone(), cpi_accounts_base);
-        token::transfer(cpi_ctx_base, amount)?;
+        let cpi_ctx_quote = CpiContext::new(cpi_program.clone(), cpi_accounts_quote);
+        token::transfer(cpi_ctx_quote, quote_amount)?;
 
-        // Transfer quote tokens from seller to buyer
-        let cpi_accounts_quote = Transfer {
-            from: ctx.accounts.seller_quote_account.to_account_info(),
-            to: ctx.accounts.buyer_quote_account.to_account_info(),
+        // Transfer base tokens from seller to buyer
+        let cpi_accounts_base = Transfer {
+            from: ctx.accounts.seller_base_account.to_account_info(),
+            to: ctx.accounts.buyer_base_account.to_account_info(),
             authority: ctx.accounts.seller.to_account_info(),
         };
-        let cpi_ctx_quote = CpiContext::new(cpi_program, cpi_accounts_quote);
-        token::transfer(cpi_ctx_quote, quote_amount)?;
+        let cpi_ctx_base = CpiContext::new(cpi_program, cpi_accounts_base);
+        token::transfer(cpi_ctx_base, amount)?;
+
+        market.last_clearing_price = price;
+        market.last_clearing_at = Clock::get()?.unix_timestamp;
+        market.total_volume = market.total_volume.checked_add(amount).unwrap();
 
         msg!("Trade settled: {} base @ {} = {} quote", amount, price, quote_amount);
         Ok(())
@@ -167,7 +196,7 @@ pub struct PlaceOrder<'info> {
     
     #[account(
         init,
-        payer = trader,
+        payer = payer,
         space = 8 + Order::INIT_SPACE,
         seeds = [
             b"order",
@@ -179,8 +208,11 @@ pub struct PlaceOrder<'info> {
     )]
     pub order: Account<'info, Order>,
     
-    #[account(mut)]
     pub trader: Signer<'info>,
+
+    /// Funds the order account; separate from the trader so program accounts can trade
+    #[account(mut)]
+    pub payer: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
@@ -206,24 +238,31 @@ pub struct ExecuteBatchAuction<'info> {
 #[derive(Accounts)]
 pub struct SettleTrade<'info> {
     #[account(
+        mut,
         seeds = [b"market", market.authority.as_ref()],
         bump = market.bump
     )]
     pub market: Account<'info, Market>,
+
+    #[account(mut)]
+    pub buy_order: Account<'info, Order>,
+
+    #[account(mut)]
+    pub sell_order: Account<'info, Order>,
     
     pub buyer: Signer<'info>,
     pub seller: Signer<'info>,
     
-    #[account(mut)]
+    #[account(mut, token::mint = market.base_mint)]
     pub buyer_base_account: Account<'info, TokenAccount>,
     
-    #[account(mut)]
+    #[account(mut, token::mint = market.quote_mint)]
     pub buyer_quote_account: Account<'info, TokenAccount>,
     
-    #[account(mut)]
+    #[account(mut, token::mint = market.base_mint)]
     pub seller_base_account: Account<'info, TokenAccount>,
     
-    #[account(mut)]
+    #[account(mut, token::mint = market.quote_mint)]
     pub seller_quote_account: Account<'info, TokenAccount>,
     
     pub token_program: Program<'info, Token>,
@@ -239,6 +278,10 @@ pub struct Market {
     pub quote_mint: Pubkey,
     pub current_batch_id: u64,
     pub total_volume: u64,
+    /// Price of the most recent settlement, quote units per base unit scaled by 1e6
+    pub last_clearing_price: u64,
+    /// Time of the most recent settlement
+    pub last_clearing_at: i64,
     pub bump: u8,
 }
 
@@ -257,6 +300,26 @@ pub struct Order {
     pub batch_id: u64,
 }
 
+impl Order {
+    /// Record a fill of `amount`, marking the order filled once nothing remains
+    pub fn fill(&mut self, amount: u64) -> Result<()> {
+        require!(
+            matches!(self.status, OrderStatus::Open | OrderStatus::PartiallyFilled),
+            DexError::OrderNotOpen
+        );
+        let filled_amount = self.filled_amount.checked_add(amount).unwrap();
+        require!(filled_amount <= self.amount, DexError::FillExceedsOrder);
+
+        self.filled_amount = filled_amount;
+        self.status = if filled_amount == self.amount {
+            OrderStatus::Filled
+        } else {
+            OrderStatus::PartiallyFilled
+        };
+        Ok(())
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, InitSpace)]
 pub enum OrderSide {
     Buy,
@@ -283,5 +346,11 @@ pub enum DexError {
     Unauthorized,
     #[msg("Insufficient liquidity")]
     InsufficientLiquidity,
+    #[msg("Orders do not form a buy and sell on this market")]
+    OrderMismatch,
+    #[msg("Settlement price is outside the orders' limit prices")]
+    PriceNotCrossed,
+    #[msg("Fill exceeds the order's remaining amount")]
+    FillExceedsOrder,
 }
 
@@ -0,0 +1,243 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{self, Mint, Token, TokenAccount, Transfer};
+
+declare_id!("UdB8h78jpdMrb1VkYqCnkZ1g9YryerTpHrRN1EHJBiG");
+
+#[program]
+pub mod chronos_mock_lending {
+    use super::*;
+
+    /// Open a lending position for a vault, with a reserve token account the position controls.
+    /// The payer becomes the position's admin.
+    pub fn initialize_position(ctx: Context<InitializePosition>) -> Result<()> {
+        let position = &mut ctx.accounts.position;
+
+        position.vault = ctx.accounts.vault.key();
+        position.reserve = ctx.accounts.reserve.key();
+        position.admin = ctx.accounts.payer.key();
+        position.principal = 0;
+        position.bump = ctx.bumps.position;
+
+        msg!("Lending position opened for vault {}", position.vault);
+        Ok(())
+    }
+
+    /// Adapter interface: lend `amount` tokens from the vault token account
+    pub fn deposit(ctx: Context<AdapterAction>, amount: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.reserve.to_account_info(),
+            authority: ctx.accounts.vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        let position = &mut ctx.accounts.position;
+        position.principal = position
+            .principal
+            .checked_add(amount)
+            .ok_or(LendingError::MathOverflow)?;
+
+        msg!("Lent {} tokens", amount);
+        Ok(())
+    }
+
+    /// Adapter interface: return `amount` tokens, principal first and then interest,
+    /// to the vault token account
+    pub fn withdraw(ctx: Context<AdapterAction>, amount: u64) -> Result<()> {
+        require!(
+            amount <= ctx.accounts.reserve.amount,
+            LendingError::InsufficientReserve
+        );
+
+        let position = &ctx.accounts.position;
+        let vault = position.vault;
+        let seeds = &[b"position".as_ref(), vault.as_ref(), &[position.bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reserve.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.position.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        let position = &mut ctx.accounts.position;
+        position.principal = position.principal.saturating_sub(amount);
+
+        msg!("Returned {} tokens", amount);
+        Ok(())
+    }
+
+    /// Adapter interface: report the position's balance, principal plus interest,
+    /// as return data
+    pub fn report_balance(ctx: Context<AdapterAction>) -> Result<u64> {
+        let balance = ctx.accounts.reserve.amount;
+
+        msg!(
+            "Position balance {} (principal {})",
+            balance,
+            ctx.accounts.position.principal
+        );
+        Ok(balance)
+    }
+
+    /// Pay `amount` of interest into a position's reserve, standing in for borrower repayments
+    pub fn accrue_interest(ctx: Context<AccrueInterest>, amount: u64) -> Result<()> {
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.payer_token_account.to_account_info(),
+            to: ctx.accounts.reserve.to_account_info(),
+            authority: ctx.accounts.payer.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        msg!("Accrued {} tokens of interest", amount);
+        Ok(())
+    }
+
+    /// Take `amount` out of a position's reserve as its admin, standing in for a borrower default
+    pub fn write_off(ctx: Context<WriteOff>, amount: u64) -> Result<()> {
+        require!(
+            amount <= ctx.accounts.reserve.amount,
+            LendingError::InsufficientReserve
+        );
+
+        let position = &ctx.accounts.position;
+        let vault = position.vault;
+        let seeds = &[b"position".as_ref(), vault.as_ref(), &[position.bump]];
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.reserve.to_account_info(),
+            to: ctx.accounts.recipient_token_account.to_account_info(),
+            authority: ctx.accounts.position.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, amount)?;
+
+        msg!("Wrote off {} tokens", amount);
+        Ok(())
+    }
+}
+
+// Account Structures
+
+#[derive(Accounts)]
+pub struct InitializePosition<'info> {
+    /// CHECK: the vault the position lends for; only recorded and used as a seed
+    pub vault: UncheckedAccount<'info>,
+
+    pub mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + LendingPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref()],
+        bump
+    )]
+    pub position: Account<'info, LendingPosition>,
+
+    #[account(
+        init,
+        payer = payer,
+        token::mint = mint,
+        token::authority = position,
+        seeds = [b"reserve", position.key().as_ref()],
+        bump
+    )]
+    pub reserve: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+/// The account layout chronos_vault passes to every adapter instruction
+#[derive(Accounts)]
+pub struct AdapterAction<'info> {
+    pub vault: Signer<'info>,
+
+    #[account(mut, token::mint = reserve.mint)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        mut,
+        seeds = [b"position", vault.key().as_ref()],
+        bump = position.bump,
+        has_one = vault,
+        has_one = reserve
+    )]
+    pub position: Account<'info, LendingPosition>,
+
+    #[account(mut)]
+    pub reserve: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AccrueInterest<'info> {
+    #[account(has_one = reserve)]
+    pub position: Account<'info, LendingPosition>,
+
+    #[account(mut)]
+    pub reserve: Account<'info, TokenAccount>,
+
+    pub payer: Signer<'info>,
+
+    #[account(mut, token::mint = reserve.mint)]
+    pub payer_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct WriteOff<'info> {
+    #[account(has_one = reserve, has_one = admin @ LendingError::Unauthorized)]
+    pub position: Account<'info, LendingPosition>,
+
+    pub admin: Signer<'info>,
+
+    #[account(mut)]
+    pub reserve: Account<'info, TokenAccount>,
+
+    #[account(mut, token::mint = reserve.mint)]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+// Data Structures
+
+/// A vault's lending position; its balance is whatever the reserve holds
+#[account]
+#[derive(InitSpace)]
+pub struct LendingPosition {
+    pub vault: Pubkey,
+    pub reserve: Pubkey,
+    /// May write off the reserve
+    pub admin: Pubkey,
+    pub principal: u64,
+    pub bump: u8,
+}
+
+// Errors
+
+#[error_code]
+pub enum LendingError {
+    #[msg("Reserve holds less than the requested amount")]
+    InsufficientReserve,
+    #[msg("Signer is not the position admin")]
+    Unauthorized,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
+}
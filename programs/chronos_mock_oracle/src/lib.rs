@@ -0,0 +1,91 @@
+use anchor_lang::prelude::*;
+
+declare_id!("2qt1xbXtJyBwTcbcczKjNaAhNuVPvPje967tR518F2BT");
+
+#[program]
+pub mod chronos_mock_oracle {
+    use super::*;
+
+    /// Create a price feed with an initial price
+    pub fn initialize_price_feed(
+        ctx: Context<InitializePriceFeed>,
+        price: i64,
+        conf: u64,
+        expo: i32,
+    ) -> Result<()> {
+        let feed = &mut ctx.accounts.price_feed;
+
+        feed.price = price;
+        feed.conf = conf;
+        feed.expo = expo;
+        feed.publish_time = Clock::get()?.unix_timestamp;
+        feed.authority = ctx.accounts.authority.key();
+
+        msg!(
+            "Price feed initialized at {} (conf {}, expo {})",
+            price,
+            conf,
+            expo
+        );
+        Ok(())
+    }
+
+    /// Publish a new price, stamped with the current time
+    pub fn set_price(ctx: Context<UpdatePriceFeed>, price: i64, conf: u64) -> Result<()> {
+        let feed = &mut ctx.accounts.price_feed;
+
+        feed.price = price;
+        feed.conf = conf;
+        feed.publish_time = Clock::get()?.unix_timestamp;
+
+        msg!("Price set to {} (conf {})", price, conf);
+        Ok(())
+    }
+
+    /// Override the publish time, for exercising staleness checks
+    pub fn set_publish_time(ctx: Context<UpdatePriceFeed>, publish_time: i64) -> Result<()> {
+        ctx.accounts.price_feed.publish_time = publish_time;
+
+        msg!("Publish time set to {}", publish_time);
+        Ok(())
+    }
+}
+
+// Account Structures
+
+#[derive(Accounts)]
+pub struct InitializePriceFeed<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + PriceFeed::INIT_SPACE
+    )]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdatePriceFeed<'info> {
+    #[account(mut, has_one = authority)]
+    pub price_feed: Account<'info, PriceFeed>,
+
+    pub authority: Signer<'info>,
+}
+
+// Data Structures
+
+/// Pyth-style price: the value is `price * 10^expo`, with `conf` in the same units.
+/// The price fields lead the account so readers can decode them without this crate.
+#[account]
+#[derive(InitSpace)]
+pub struct PriceFeed {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+    pub authority: Pubkey,
+}
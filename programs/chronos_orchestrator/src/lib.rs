@@ -127,6 +127,38 @@ pub mod chronos_orchestrator {
         Ok(())
     }
 
+    /// Mark a reservation as executed (called by the requester)
+    pub fn mark_reservation_executed(ctx: Context<UpdateReservation>) -> Result<()> {
+        let reservation = &mut ctx.accounts.reservation;
+
+        require!(reservation.status.is_active(), OrchestratorError::ReservationNotActive);
+        reservation.status = ReservationStatus::Executed;
+
+        msg!("Slot reservation executed: {}", reservation.slot_time);
+        Ok(())
+    }
+
+    /// Mark a reservation as failed (called by the requester)
+    pub fn mark_reservation_failed(ctx: Context<UpdateReservation>) -> Result<()> {
+        let reservation = &mut ctx.accounts.reservation;
+
+        require!(reservation.status.is_active(), OrchestratorError::ReservationNotActive);
+        reservation.status = ReservationStatus::Failed;
+
+        msg!("Slot reservation failed: {}", reservation.slot_time);
+        Ok(())
+    }
+
+    /// Cancel an active reservation and close its account (called by the requester)
+    pub fn cancel_reservation(ctx: Context<CancelReservation>) -> Result<()> {
+        let reservation = &ctx.accounts.reservation;
+
+        require!(reservation.status.is_active(), OrchestratorError::ReservationNotActive);
+
+        msg!("Slot reservation cancelled: {}", reservation.slot_time);
+        Ok(())
+    }
+
     /// Handle execution failure and rollback
     pub fn handle_execution_failure(
         ctx: Context<HandleExecutionFailure>,
@@ -197,7 +229,7 @@ pub struct ReserveRaikuSlot<'info> {
     
     #[account(
         init,
-        payer = requester,
+        payer = payer,
         space = 8 + SlotReservation::INIT_SPACE,
         seeds = [
             b"reservation",
@@ -208,8 +240,10 @@ pub struct ReserveRaikuSlot<'info> {
     )]
     pub reservation: Account<'info, SlotReservation>,
     
-    #[account(mut)]
     pub requester: Signer<'info>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
     
     pub system_program: Program<'info, System>,
 }
@@ -256,6 +290,26 @@ pub struct ConfirmSlotReservation<'info> {
     pub reservation: Account<'info, SlotReservation>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateReservation<'info> {
+    #[account(mut, has_one = requester)]
+    pub reservation: Account<'info, SlotReservation>,
+
+    pub requester: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CancelReservation<'info> {
+    #[account(mut, has_one = requester, close = rent_receiver)]
+    pub reservation: Account<'info, SlotReservation>,
+
+    pub requester: Signer<'info>,
+
+    /// CHECK: receives the reservation's rent, chosen by the requester
+    #[account(mut)]
+    pub rent_receiver: UncheckedAccount<'info>,
+}
+
 #[derive(Accounts)]
 pub struct HandleExecutionFailure<'info> {
     #[account(
@@ -329,6 +383,13 @@ pub enum ReservationStatus {
     Expired,
 }
 
+impl ReservationStatus {
+    /// Pending and confirmed reservations can still be executed
+    pub fn is_active(&self) -> bool {
+        matches!(self, ReservationStatus::Pending | ReservationStatus::Confirmed)
+    }
+}
+
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, InitSpace)]
 pub enum BatchStatus {
     Pending,
@@ -357,5 +418,7 @@ pub enum OrchestratorError {
     BatchNotPending,
     #[msg("Invalid confirmation ID")]
     InvalidConfirmation,
+    #[msg("Reservation is no longer active")]
+    ReservationNotActive,
 }
 
@@ -0,0 +1,141 @@
+//! The CPI interface strategy adapters implement.
+//!
+//! An adapter program exposes three Anchor-style global instructions, each taking the
+//! accounts, in order: the vault (signer), the vault token account (writable), the adapter
+//! position (writable), the adapter token account (writable) and the token program.
+//!
+//! - `deposit(amount: u64)` moves `amount` tokens from the vault token account into the adapter.
+//! - `withdraw(amount: u64)` moves `amount` tokens from the adapter to the vault token account.
+//! - `report_balance()` sets the position's current value, principal plus yield, in vault
+//!   asset units as an 8-byte little-endian `u64` of return data.
+//!
+//! `chronos_mock_lending` is a reference implementation.
+
+use crate::VaultError;
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::hash::hash;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::{get_return_data, invoke_signed};
+
+/// Number of remaining accounts the caller supplies per registered adapter
+pub const ACCOUNTS_PER_ADAPTER: usize = 3;
+
+/// Accounts of a registered adapter, taken from the instruction's remaining accounts
+/// in the order: adapter program, adapter position, adapter token account
+pub struct AdapterAccounts<'info> {
+    pub program: AccountInfo<'info>,
+    pub position: AccountInfo<'info>,
+    pub token_account: AccountInfo<'info>,
+}
+
+/// Accounts owned by the vault that take part in every adapter call
+pub struct VaultAccounts<'info> {
+    pub vault: AccountInfo<'info>,
+    pub vault_token_account: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+}
+
+/// Move `amount` tokens from the vault token account into the adapter
+pub fn deposit<'info>(
+    vault: &VaultAccounts<'info>,
+    adapter: &AdapterAccounts<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    invoke_adapter(
+        vault,
+        adapter,
+        signer_seeds,
+        instruction_data("deposit", Some(amount)),
+    )
+}
+
+/// Move `amount` tokens from the adapter back into the vault token account
+pub fn withdraw<'info>(
+    vault: &VaultAccounts<'info>,
+    adapter: &AdapterAccounts<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    invoke_adapter(
+        vault,
+        adapter,
+        signer_seeds,
+        instruction_data("withdraw", Some(amount)),
+    )
+}
+
+/// Ask the adapter for the current value of the vault's position
+pub fn report_balance<'info>(
+    vault: &VaultAccounts<'info>,
+    adapter: &AdapterAccounts<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<u64> {
+    invoke_adapter(
+        vault,
+        adapter,
+        signer_seeds,
+        instruction_data("report_balance", None),
+    )?;
+
+    let (program_id, data) = get_return_data().ok_or(VaultError::InvalidAdapterBalance)?;
+    require_keys_eq!(
+        program_id,
+        adapter.program.key(),
+        VaultError::InvalidAdapterBalance
+    );
+    let balance: [u8; 8] = data
+        .as_slice()
+        .try_into()
+        .map_err(|_| error!(VaultError::InvalidAdapterBalance))?;
+    Ok(u64::from_le_bytes(balance))
+}
+
+fn instruction_data(name: &str, amount: Option<u64>) -> Vec<u8> {
+    let mut data = sighash(name).to_vec();
+    if let Some(amount) = amount {
+        data.extend_from_slice(&amount.to_le_bytes());
+    }
+    data
+}
+
+fn invoke_adapter<'info>(
+    vault: &VaultAccounts<'info>,
+    adapter: &AdapterAccounts<'info>,
+    signer_seeds: &[&[&[u8]]],
+    data: Vec<u8>,
+) -> Result<()> {
+    let ix = Instruction {
+        program_id: adapter.program.key(),
+        accounts: vec![
+            AccountMeta::new_readonly(vault.vault.key(), true),
+            AccountMeta::new(vault.vault_token_account.key(), false),
+            AccountMeta::new(adapter.position.key(), false),
+            AccountMeta::new(adapter.token_account.key(), false),
+            AccountMeta::new_readonly(vault.token_program.key(), false),
+        ],
+        data,
+    };
+
+    invoke_signed(
+        &ix,
+        &[
+            vault.vault.clone(),
+            vault.vault_token_account.clone(),
+            adapter.position.clone(),
+            adapter.token_account.clone(),
+            vault.token_program.clone(),
+            adapter.program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Anchor instruction discriminator for a global instruction `name`
+pub(crate) fn sighash(name: &str) -> [u8; 8] {
+    let preimage = format!("global:{}", name);
+    let mut discriminator = [0u8; 8];
+    discriminator.copy_from_slice(&hash(preimage.as_bytes()).to_bytes()[..8]);
+    discriminator
+}
@@ -0,0 +1,233 @@
+use crate::adapter::sighash;
+use crate::{convert_to_assets, convert_to_shares, Vault, VaultError};
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::instruction::{AccountMeta, Instruction};
+use anchor_lang::solana_program::program::invoke_signed;
+use anchor_spl::token::TokenAccount;
+
+/// Number of remaining accounts the caller supplies per registered child vault
+pub const ACCOUNTS_PER_CHILD: usize = 6;
+
+/// Accounts of a registered child vault, taken from the instruction's remaining accounts
+/// in the order: child vault, child vault token account, child share mint, the allocator's
+/// child share account, the allocator's position in the child and the child's share history
+pub struct ChildAccounts<'info> {
+    pub vault: AccountInfo<'info>,
+    pub vault_token_account: AccountInfo<'info>,
+    pub share_mint: AccountInfo<'info>,
+    pub share_account: AccountInfo<'info>,
+    pub position: AccountInfo<'info>,
+    pub history: AccountInfo<'info>,
+}
+
+/// Accounts of the allocator vault that take part in every child vault call
+pub struct AllocatorAccounts<'info> {
+    pub vault: AccountInfo<'info>,
+    pub vault_token_account: AccountInfo<'info>,
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+/// A child vault's state as seen by its allocator
+pub struct ChildState {
+    pub vault: Vault,
+    pub liquid_assets: u64,
+    pub shares: u64,
+}
+
+impl ChildState {
+    /// Decode a child vault, its liquid balance and the allocator's share balance
+    pub fn load(child: &ChildAccounts) -> Result<Self> {
+        require_keys_eq!(*child.vault.owner, crate::ID, VaultError::InvalidChildVault);
+        let vault = Vault::try_deserialize(&mut &child.vault.try_borrow_data()?[..])?;
+        require_keys_eq!(
+            child.vault_token_account.key(),
+            vault.vault_token_account,
+            VaultError::InvalidChildVaultAccounts
+        );
+
+        let liquid_assets =
+            TokenAccount::try_deserialize(&mut &child.vault_token_account.try_borrow_data()?[..])?
+                .amount;
+        let shares =
+            TokenAccount::try_deserialize(&mut &child.share_account.try_borrow_data()?[..])?.amount;
+
+        Ok(Self {
+            vault,
+            liquid_assets,
+            shares,
+        })
+    }
+
+    pub fn total_assets(&self) -> Result<u64> {
+        self.vault.total_assets(self.liquid_assets)
+    }
+
+    /// Value of the allocator's shares, in vault asset units
+    pub fn value(&self) -> Result<u64> {
+        convert_to_assets(
+            self.shares,
+            self.total_assets()?,
+            self.vault.share_supply()?,
+        )
+    }
+
+    /// Shares the child can redeem instantly for up to `assets`
+    pub fn redeemable_shares(&self, assets: u64) -> Result<u64> {
+        let assets = assets.min(self.vault.available_liquidity(self.liquid_assets));
+        Ok(
+            convert_to_shares(assets, self.total_assets()?, self.vault.share_supply()?)?
+                .min(self.shares),
+        )
+    }
+}
+
+/// Parse the per-child remaining accounts, checking them against the vault's registry
+pub fn child_accounts<'info>(
+    vault: &Vault,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<Vec<ChildAccounts<'info>>> {
+    require!(
+        remaining_accounts.len() == vault.children.len() * ACCOUNTS_PER_CHILD,
+        VaultError::InvalidChildVaultAccounts
+    );
+
+    vault
+        .children
+        .iter()
+        .zip(remaining_accounts.chunks(ACCOUNTS_PER_CHILD))
+        .map(|(child, accounts)| {
+            require_keys_eq!(
+                accounts[0].key(),
+                child.vault,
+                VaultError::InvalidChildVaultAccounts
+            );
+            require_keys_eq!(
+                accounts[3].key(),
+                child.share_account,
+                VaultError::InvalidChildVaultAccounts
+            );
+            Ok(ChildAccounts {
+                vault: accounts[0].clone(),
+                vault_token_account: accounts[1].clone(),
+                share_mint: accounts[2].clone(),
+                share_account: accounts[3].clone(),
+                position: accounts[4].clone(),
+                history: accounts[5].clone(),
+            })
+        })
+        .collect()
+}
+
+/// Re-value every child position from the child's share price, caching the total on the vault
+pub fn update_children_value(vault: &mut Vault, children: &[ChildAccounts]) -> Result<u64> {
+    let mut total: u64 = 0;
+    for (registered, accounts) in vault.children.iter_mut().zip(children) {
+        registered.last_value = ChildState::load(accounts)?.value()?;
+        total = total
+            .checked_add(registered.last_value)
+            .ok_or(error!(VaultError::MathOverflow))?;
+    }
+
+    vault.children_value = total;
+    Ok(total)
+}
+
+/// Deposit `amount` tokens from the allocator into a child vault
+pub fn deposit<'info>(
+    allocator: &AllocatorAccounts<'info>,
+    child: &ChildAccounts<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+) -> Result<()> {
+    let mut data = sighash("deposit").to_vec();
+    data.extend_from_slice(&amount.to_le_bytes());
+    data.extend_from_slice(&0u64.to_le_bytes());
+
+    let ix = Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(child.vault.key(), false),
+            AccountMeta::new(child.position.key(), false),
+            AccountMeta::new(allocator.vault.key(), true),
+            AccountMeta::new(allocator.vault_token_account.key(), false),
+            AccountMeta::new(child.vault_token_account.key(), false),
+            AccountMeta::new(child.share_mint.key(), false),
+            AccountMeta::new(child.history.key(), false),
+            AccountMeta::new(child.share_account.key(), false),
+            // No allowlist entry: Anchor reads the program id as an absent optional account
+            AccountMeta::new_readonly(crate::ID, false),
+            AccountMeta::new_readonly(allocator.token_program.key(), false),
+            AccountMeta::new_readonly(allocator.associated_token_program.key(), false),
+            AccountMeta::new_readonly(allocator.system_program.key(), false),
+        ],
+        data,
+    };
+
+    invoke_signed(
+        &ix,
+        &[
+            child.vault.clone(),
+            child.position.clone(),
+            allocator.vault.clone(),
+            allocator.vault_token_account.clone(),
+            child.vault_token_account.clone(),
+            child.share_mint.clone(),
+            child.history.clone(),
+            child.share_account.clone(),
+            allocator.token_program.clone(),
+            allocator.associated_token_program.clone(),
+            allocator.system_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
+
+/// Redeem `shares` of a child vault back into the allocator's token account
+pub fn withdraw<'info>(
+    allocator: &AllocatorAccounts<'info>,
+    child: &ChildAccounts<'info>,
+    signer_seeds: &[&[&[u8]]],
+    shares: u64,
+) -> Result<()> {
+    let mut data = sighash("withdraw").to_vec();
+    data.extend_from_slice(&shares.to_le_bytes());
+    data.extend_from_slice(&0u64.to_le_bytes());
+
+    let ix = Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(child.vault.key(), false),
+            AccountMeta::new(child.position.key(), false),
+            AccountMeta::new(allocator.vault.key(), true),
+            AccountMeta::new(allocator.vault_token_account.key(), false),
+            AccountMeta::new(child.vault_token_account.key(), false),
+            AccountMeta::new(child.share_mint.key(), false),
+            AccountMeta::new(child.history.key(), false),
+            AccountMeta::new(child.share_account.key(), false),
+            AccountMeta::new_readonly(allocator.token_program.key(), false),
+            AccountMeta::new_readonly(allocator.system_program.key(), false),
+        ],
+        data,
+    };
+
+    invoke_signed(
+        &ix,
+        &[
+            child.vault.clone(),
+            child.position.clone(),
+            allocator.vault.clone(),
+            allocator.vault_token_account.clone(),
+            child.vault_token_account.clone(),
+            child.share_mint.clone(),
+            child.history.clone(),
+            child.share_account.clone(),
+            allocator.token_program.clone(),
+            allocator.system_program.clone(),
+        ],
+        signer_seeds,
+    )?;
+    Ok(())
+}
@@ -0,0 +1,59 @@
+use anchor_lang::prelude::*;
+use chronos_dex::cpi::accounts::{CancelOrder, PlaceOrder, SettleTrade};
+use chronos_dex::OrderSide;
+
+/// chronos_dex prices are quote units per base unit, scaled by this factor
+pub const PRICE_SCALE: u64 = 1_000_000;
+
+/// Place a DEX order with the vault as trader; `payer` funds the order account
+#[allow(clippy::too_many_arguments)]
+pub fn place_order<'info>(
+    dex_program: AccountInfo<'info>,
+    market: AccountInfo<'info>,
+    order: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    side: OrderSide,
+    price: u64,
+    amount: u64,
+    slot_reservation_time: i64,
+) -> Result<()> {
+    let cpi_accounts = PlaceOrder {
+        market,
+        order,
+        trader: vault,
+        payer,
+        system_program,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(dex_program, cpi_accounts, signer_seeds);
+    chronos_dex::cpi::place_order(cpi_ctx, side, price, amount, slot_reservation_time)
+}
+
+/// Cancel an open DEX order, signing as the vault that placed it
+pub fn cancel_order<'info>(
+    dex_program: AccountInfo<'info>,
+    order: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_accounts = CancelOrder {
+        order,
+        trader: vault,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(dex_program, cpi_accounts, signer_seeds);
+    chronos_dex::cpi::cancel_order(cpi_ctx)
+}
+
+/// Settle a crossed buy and sell order at `price`, signing as the vault for its side
+pub fn settle_trade<'info>(
+    dex_program: AccountInfo<'info>,
+    accounts: SettleTrade<'info>,
+    signer_seeds: &[&[&[u8]]],
+    amount: u64,
+    price: u64,
+) -> Result<()> {
+    let cpi_ctx = CpiContext::new_with_signer(dex_program, accounts, signer_seeds);
+    chronos_dex::cpi::settle_trade(cpi_ctx, amount, price)
+}
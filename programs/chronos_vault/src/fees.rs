@@ -0,0 +1,87 @@
+use crate::{Vault, VaultError, VIRTUAL_ASSETS, VIRTUAL_SHARES};
+use anchor_lang::prelude::*;
+
+/// Scale of share prices: assets paid out per `PRICE_PRECISION` shares
+pub const PRICE_PRECISION: u64 = 1_000_000_000;
+/// Upper bound on the annual management fee (5%)
+pub const MAX_MANAGEMENT_FEE_BPS: u16 = 500;
+/// Upper bound on the performance fee (30%)
+pub const MAX_PERFORMANCE_FEE_BPS: u16 = 3_000;
+/// Delay between proposing and applying a fee change
+pub const FEE_CHANGE_TIMELOCK: i64 = 7 * 24 * 60 * 60;
+/// Scale of the vault fee index: fee assets charged per share
+pub const FEE_INDEX_PRECISION: u128 = 1_000_000_000_000_000_000;
+/// Length of the year fees and APYs are annualized over
+pub const SECONDS_PER_YEAR: i64 = 365 * 24 * 60 * 60;
+
+const BPS_DENOMINATOR: u128 = 10_000;
+
+/// Share price against the virtual offsets, scaled by `PRICE_PRECISION`
+pub fn share_price(total_assets: u64, share_supply: u64) -> Result<u64> {
+    let price = (total_assets as u128 + VIRTUAL_ASSETS as u128)
+        .checked_mul(PRICE_PRECISION as u128)
+        .ok_or(error!(VaultError::MathOverflow))?
+        / (share_supply as u128 + VIRTUAL_SHARES as u128);
+    u64::try_from(price).map_err(|_| error!(VaultError::MathOverflow))
+}
+
+/// Accrue management and performance fees up to `now` as unminted fee shares.
+/// Returns the number of fee shares added to `vault.accrued_fee_shares`.
+/// A shut-down vault no longer charges fees.
+pub fn accrue(vault: &mut Vault, total_assets: u64, now: i64) -> Result<u64> {
+    let supply = vault.share_supply()?;
+    let elapsed = now.saturating_sub(vault.last_fee_accrual).max(0) as u128;
+    vault.last_fee_accrual = now;
+
+    if supply == 0 || total_assets == 0 || vault.is_shutdown {
+        return Ok(0);
+    }
+
+    // Management fee: annualized share of assets under management
+    let management_fee = (total_assets as u128)
+        .checked_mul(vault.management_fee_bps as u128)
+        .and_then(|v| v.checked_mul(elapsed))
+        .ok_or(error!(VaultError::MathOverflow))?
+        / (BPS_DENOMINATOR * SECONDS_PER_YEAR as u128);
+
+    // Performance fee: share of the gain in share price above the high-water mark
+    let price = share_price(total_assets, supply)?;
+    let performance_fee = if price > vault.high_water_mark {
+        let gain = ((price - vault.high_water_mark) as u128)
+            .checked_mul(supply as u128)
+            .ok_or(error!(VaultError::MathOverflow))?
+            / PRICE_PRECISION as u128;
+        gain.checked_mul(vault.performance_fee_bps as u128)
+            .ok_or(error!(VaultError::MathOverflow))?
+            / BPS_DENOMINATOR
+    } else {
+        0
+    };
+
+    // Issue enough shares that they are worth the fee after dilution
+    let fee_assets = (management_fee + performance_fee).min(total_assets as u128 / 2);
+    let fee_shares = fee_assets
+        .checked_mul(supply as u128 + VIRTUAL_SHARES as u128)
+        .ok_or(error!(VaultError::MathOverflow))?
+        / (total_assets as u128 + VIRTUAL_ASSETS as u128 - fee_assets);
+    let fee_shares = u64::try_from(fee_shares).map_err(|_| error!(VaultError::MathOverflow))?;
+
+    // Track cumulative fees per share so positions can attribute what they paid
+    vault.fee_index = fee_assets
+        .checked_mul(FEE_INDEX_PRECISION)
+        .map(|v| v / supply as u128)
+        .and_then(|v| vault.fee_index.checked_add(v))
+        .ok_or(error!(VaultError::MathOverflow))?;
+
+    vault.accrued_fee_shares = vault
+        .accrued_fee_shares
+        .checked_add(fee_shares)
+        .ok_or(error!(VaultError::MathOverflow))?;
+
+    let price_after = share_price(total_assets, vault.share_supply()?)?;
+    if price_after > vault.high_water_mark {
+        vault.high_water_mark = price_after;
+    }
+
+    Ok(fee_shares)
+}
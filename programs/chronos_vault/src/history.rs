@@ -0,0 +1,50 @@
+use crate::{fees, ShareHistory, ShareSnapshot, Vault, VaultError, SHARE_HISTORY_LEN};
+use anchor_lang::prelude::*;
+
+/// Append a snapshot of the vault after a change to its assets or shares, overwriting the
+/// oldest snapshot once the buffer is full
+pub fn record(
+    history: &mut ShareHistory,
+    vault: &Vault,
+    total_assets: u64,
+    now: i64,
+) -> Result<()> {
+    let head = history.head as usize;
+    history.snapshots[head] = ShareSnapshot {
+        timestamp: now,
+        nav: total_assets,
+        share_price: fees::share_price(total_assets, vault.share_supply()?)?,
+        total_shares: vault.total_shares,
+    };
+    history.head = ((head + 1) % SHARE_HISTORY_LEN) as u32;
+    history.len = (history.len + 1).min(SHARE_HISTORY_LEN as u32);
+    Ok(())
+}
+
+/// The snapshot `age` records before the newest one
+fn nth_latest(history: &ShareHistory, age: usize) -> &ShareSnapshot {
+    let index = (history.head as usize + SHARE_HISTORY_LEN - 1 - age) % SHARE_HISTORY_LEN;
+    &history.snapshots[index]
+}
+
+/// Share price growth, in bps per year, from the newest snapshot at least `window` seconds
+/// before the latest one up to the latest one. Growth is annualized linearly, without
+/// compounding, and is negative when the share price fell.
+pub fn apy_bps(history: &ShareHistory, window: i64) -> Result<i64> {
+    require!(window > 0, VaultError::InvalidHistoryWindow);
+    require!(history.len > 0, VaultError::InsufficientHistory);
+
+    let latest = nth_latest(history, 0);
+    let start = (1..history.len as usize)
+        .map(|age| nth_latest(history, age))
+        .find(|s| latest.timestamp - s.timestamp >= window)
+        .ok_or(VaultError::InsufficientHistory)?;
+    require!(start.share_price > 0, VaultError::InvalidHistorySnapshot);
+
+    let growth = ((latest.share_price as i128 - start.share_price as i128) * 10_000)
+        .checked_div(start.share_price as i128)
+        .ok_or(VaultError::MathOverflow)?;
+    let elapsed = (latest.timestamp - start.timestamp) as i128;
+    let apy = growth * fees::SECONDS_PER_YEAR as i128 / elapsed;
+    i64::try_from(apy).map_err(|_| error!(VaultError::MathOverflow))
+}
@@ -1,49 +1,237 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, TokenAccount, Transfer};
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{self, Burn, Mint, MintTo, Token, TokenAccount, Transfer};
+use chronos_dex::cpi::accounts::SettleTrade;
+use chronos_dex::program::ChronosDex;
+use chronos_dex::{Market as DexMarket, Order as DexOrder, OrderSide, OrderStatus};
+use chronos_orchestrator::program::ChronosOrchestrator;
+use chronos_orchestrator::{
+    Orchestrator, RaikuReservationType, ReservationStatus, SlotReservation as OrchestratorReservation,
+};
+
+pub mod adapter;
+pub mod allocator;
+pub mod dex;
+pub mod fees;
+pub mod history;
+pub mod nav;
+pub mod reservation;
+pub mod risk;
+pub mod schedule;
+pub mod strategy;
 
 declare_id!("EjG3EGtjpC9VtgrzuW6aJ55KcJuWF5buuvhF4S5B7EcP");
 
+/// Virtual shares added to the supply in share conversions (decimals offset of 3)
+pub const VIRTUAL_SHARES: u64 = 1_000;
+/// Virtual assets added to total assets in share conversions
+pub const VIRTUAL_ASSETS: u64 = 1;
+/// Minimum deposit accepted while the vault has no shares outstanding, in whole asset tokens
+pub const MIN_INITIAL_DEPOSIT_TOKENS: u64 = 1;
+/// Largest asset mint decimals accepted, so that a whole asset token fits in a u64
+pub const MAX_ASSET_DECIMALS: u8 = 18;
+/// Maximum number of strategy adapters registered on a vault
+pub const MAX_ADAPTERS: usize = 4;
+/// Maximum number of oracle-priced holdings registered on a vault
+pub const MAX_HOLDINGS: usize = 4;
+/// Maximum number of child vaults an allocator vault invests in
+pub const MAX_CHILD_VAULTS: usize = 4;
+/// Maximum number of allowlisted keepers on a vault
+pub const MAX_KEEPERS: usize = 5;
+/// Default delay after a rebalance falls due before anyone may execute it
+pub const DEFAULT_KEEPER_GRACE_PERIOD: i64 = 600;
+/// Maximum number of slot reservations held on a vault
+pub const MAX_RESERVATIONS: usize = 10;
+/// Default seconds either side of a reservation's slot time in which execution consumes it
+pub const DEFAULT_SLOT_TOLERANCE: i64 = 30;
+/// Upper bound on a vault's slot tolerance
+pub const MAX_SLOT_TOLERANCE: i64 = 300;
+/// Upper bound on a vault's rebalance frequency
+pub const MAX_REBALANCE_FREQUENCY: i64 = 30 * 24 * 60 * 60;
+/// Number of snapshots kept in a vault's share history
+pub const SHARE_HISTORY_LEN: usize = 256;
+/// Delay between proposing and applying a strategy type or risk level change
+pub const STRATEGY_CHANGE_TIMELOCK: i64 = 3 * 24 * 60 * 60;
+
+/// Signer seeds for CPIs where the vault PDA is the authority
+macro_rules! vault_signer_seeds {
+    ($vault:expr) => {
+        &[b"vault".as_ref(), $vault.creator.as_ref(), &$vault.vault_id.to_le_bytes(), &[$vault.bump]]
+    };
+}
+
 #[program]
 pub mod chronos_vault {
     use super::*;
 
-    /// Initialize a new Temporal Vault with strategy parameters
+    /// Create the program-wide vault registry
+    pub fn initialize_registry(ctx: Context<InitializeRegistry>) -> Result<()> {
+        let registry = &mut ctx.accounts.registry;
+        registry.vault_count = 0;
+        registry.bump = ctx.bumps.registry;
+
+        msg!("Vault registry initialized");
+        Ok(())
+    }
+
+    /// Initialize a new Temporal Vault with strategy parameters.
+    /// `vault_id` distinguishes the vaults of a single creator.
     pub fn initialize_vault(
         ctx: Context<InitializeVault>,
+        vault_id: u64,
         strategy_type: StrategyType,
         risk_level: u8,
         rebalance_frequency: i64,
     ) -> Result<()> {
+        risk::check_strategy(risk_level, strategy_type)?;
+        check_rebalance_frequency(rebalance_frequency)?;
+        require!(
+            ctx.accounts.asset_mint.decimals <= MAX_ASSET_DECIMALS,
+            VaultError::UnsupportedAssetDecimals
+        );
+
         let vault = &mut ctx.accounts.vault;
         
+        vault.creator = ctx.accounts.authority.key();
+        vault.vault_id = vault_id;
+        vault.registry_index = ctx.accounts.registry.vault_count;
         vault.authority = ctx.accounts.authority.key();
+        vault.pending_authority = None;
+        vault.asset_mint = ctx.accounts.asset_mint.key();
+        vault.vault_token_account = ctx.accounts.vault_token_account.key();
+        vault.share_mint = ctx.accounts.share_mint.key();
+        vault.asset_decimals = ctx.accounts.asset_mint.decimals;
         vault.strategy_type = strategy_type;
         vault.risk_level = risk_level;
         vault.rebalance_frequency = rebalance_frequency;
-        vault.total_deposits = 0;
+        vault.deployed_assets = 0;
         vault.total_shares = 0;
         vault.last_rebalance = Clock::get()?.unix_timestamp;
         vault.reserved_slots = Vec::new();
+        vault.adapters = Vec::new();
+        vault.holdings = Vec::new();
+        vault.holdings_value = 0;
+        vault.children = Vec::new();
+        vault.children_value = 0;
+        vault.hedge_orders = Vec::new();
+        vault.arbitrage = None;
+        vault.nav_updated_at = 0;
+        vault.keepers = Vec::new();
+        vault.keeper_reward = 0;
+        vault.keeper_grace_period = DEFAULT_KEEPER_GRACE_PERIOD;
+        vault.execution_mode = ExecutionMode::Interval;
+        vault.slot_tolerance = DEFAULT_SLOT_TOLERANCE;
+        vault.last_nav_before = 0;
+        vault.last_nav_after = 0;
+        vault.last_share_price = 0;
+        vault.fee_recipient = ctx.accounts.authority.key();
+        vault.management_fee_bps = 0;
+        vault.performance_fee_bps = 0;
+        vault.high_water_mark = fees::share_price(0, 0)?;
+        vault.last_fee_accrual = vault.last_rebalance;
+        vault.accrued_fee_shares = 0;
+        vault.fee_index = 0;
+        vault.pending_fee_change = None;
+        vault.withdrawal_epoch = 0;
+        vault.queued_withdrawal_assets = 0;
+        vault.claimable_withdrawal_assets = 0;
+        vault.guardian = ctx.accounts.authority.key();
+        vault.deposits_paused = false;
+        vault.withdrawals_paused = false;
+        vault.execution_paused = false;
+        vault.is_shutdown = false;
+        vault.pending_strategy_change = None;
+        vault.deposit_cap = 0;
+        vault.user_deposit_cap = 0;
+        vault.allowlist_enabled = false;
+        vault.schedule = RebalanceSchedule {
+            kind: ScheduleKind::Interval,
+            deviation_trigger_bps: 0,
+            auto_reserve: None,
+        };
         vault.bump = ctx.bumps.vault;
-        
+
+        let mut history = ctx.accounts.history.load_init()?;
+        history.vault = vault.key();
+        history.bump = ctx.bumps.history;
+
+        let entry = &mut ctx.accounts.registry_entry;
+        entry.vault = vault.key();
+        entry.creator = vault.creator;
+        entry.vault_id = vault_id;
+        entry.asset_mint = vault.asset_mint;
+        entry.strategy_type = strategy_type;
+        entry.created_at = vault.last_rebalance;
+        entry.bump = ctx.bumps.registry_entry;
+
+        let registry = &mut ctx.accounts.registry;
+        registry.vault_count = registry.vault_count.checked_add(1).unwrap();
+
+        emit!(VaultInitialized {
+            vault: vault.key(),
+            creator: vault.creator,
+            vault_id,
+            registry_index: vault.registry_index,
+            authority: vault.authority,
+            asset_mint: vault.asset_mint,
+            share_mint: vault.share_mint,
+            strategy_type,
+            risk_level,
+            rebalance_frequency,
+            timestamp: vault.last_rebalance,
+        });
         msg!("Temporal Vault initialized with strategy: {:?}", strategy_type);
         Ok(())
     }
 
-    /// Deposit funds into the vault
-    pub fn deposit(ctx: Context<Deposit>, amount: u64) -> Result<()> {
+    /// Deposit funds into the vault, reverting if fewer than `min_shares_out` shares are minted
+    pub fn deposit(ctx: Context<Deposit>, amount: u64, min_shares_out: u64) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
-        
-        // Calculate shares to mint
-        let shares_to_mint = if vault.total_shares == 0 {
-            amount
-        } else {
-            (amount as u128)
-                .checked_mul(vault.total_shares as u128)
-                .unwrap()
-                .checked_div(vault.total_deposits as u128)
-                .unwrap() as u64
-        };
+
+        require!(!vault.is_shutdown, VaultError::VaultIsShutdown);
+        require!(!vault.deposits_paused, VaultError::DepositsPaused);
+
+        if vault.share_supply()? == 0 {
+            require!(amount >= vault.min_initial_deposit()?, VaultError::DepositTooSmall);
+        }
+
+        // Guarded launches: only allowlisted depositors, who must present their entry
+        if vault.allowlist_enabled {
+            require!(
+                ctx.accounts.allowlist_entry.is_some(),
+                VaultError::NotAllowlisted
+            );
+        }
+
+        // Settle fees so the new depositor does not pay for time before their deposit
+        let clock = Clock::get()?;
+        nav::check_fresh(vault, clock.unix_timestamp)?;
+        let total_assets = vault.total_assets(ctx.accounts.vault_token_account.amount)?;
+        fees::accrue(vault, total_assets, clock.unix_timestamp)?;
+        let share_price = fees::share_price(total_assets, vault.share_supply()?)?;
+
+        if vault.deposit_cap > 0 {
+            require!(
+                total_assets.checked_add(amount).ok_or(VaultError::MathOverflow)? <= vault.deposit_cap,
+                VaultError::DepositCapExceeded
+            );
+        }
+        if vault.user_deposit_cap > 0 {
+            let user_assets = convert_to_assets(
+                ctx.accounts.user_share_account.amount,
+                total_assets,
+                vault.share_supply()?,
+            )?;
+            require!(
+                user_assets.checked_add(amount).ok_or(VaultError::MathOverflow)? <= vault.user_deposit_cap,
+                VaultError::UserDepositCapExceeded
+            );
+        }
+
+        // Calculate shares to mint, rounding down in the vault's favour
+        let shares_to_mint = convert_to_shares(amount, total_assets, vault.share_supply()?)?;
+        require!(shares_to_mint > 0, VaultError::ZeroShares);
+        require!(shares_to_mint >= min_shares_out, VaultError::SlippageExceeded);
 
         // Transfer tokens to vault
         let cpi_accounts = Transfer {
@@ -55,39 +243,95 @@ pub mod chronos_vault {
         let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
         token::transfer(cpi_ctx, amount)?;
 
+        // Mint share tokens to the depositor
+        let seeds = vault_signer_seeds!(vault);
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            to: ctx.accounts.user_share_account.to_account_info(),
+            authority: vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::mint_to(cpi_ctx, shares_to_mint)?;
+
         // Update vault state
-        vault.total_deposits = vault.total_deposits.checked_add(amount).unwrap();
         vault.total_shares = vault.total_shares.checked_add(shares_to_mint).unwrap();
 
+        ctx.accounts.share_mint.reload()?;
+        require!(
+            ctx.accounts.share_mint.supply == vault.total_shares,
+            VaultError::ShareSupplyMismatch
+        );
+
         // Update user position
         let user_position = &mut ctx.accounts.user_position;
-        user_position.shares = user_position.shares.checked_add(shares_to_mint).unwrap();
-        user_position.deposited_amount = user_position.deposited_amount.checked_add(amount).unwrap();
+        user_position.settle_fees(vault.fee_index)?;
+        user_position.record_deposit(amount, shares_to_mint, clock.unix_timestamp)?;
+
+        history::record(
+            &mut *ctx.accounts.history.load_mut()?,
+            vault,
+            total_assets.checked_add(amount).ok_or(VaultError::MathOverflow)?,
+            clock.unix_timestamp,
+        )?;
 
+        emit!(Deposited {
+            vault: vault.key(),
+            user: ctx.accounts.user.key(),
+            assets: amount,
+            shares: shares_to_mint,
+            share_price,
+            total_shares: vault.total_shares,
+            timestamp: clock.unix_timestamp,
+        });
         msg!("Deposited {} tokens, minted {} shares", amount, shares_to_mint);
         Ok(())
     }
 
-    /// Withdraw funds from the vault
-    pub fn withdraw(ctx: Context<Withdraw>, shares: u64) -> Result<()> {
+    /// Withdraw funds from the vault, reverting if fewer than `min_assets_out` tokens are returned
+    pub fn withdraw(ctx: Context<Withdraw>, shares: u64, min_assets_out: u64) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
-        let user_position = &mut ctx.accounts.user_position;
 
-        require!(user_position.shares >= shares, VaultError::InsufficientShares);
+        require!(!vault.withdrawals_paused, VaultError::WithdrawalsPaused);
+
+        require!(
+            ctx.accounts.user_share_account.amount >= shares,
+            VaultError::InsufficientShares
+        );
+
+        let clock = Clock::get()?;
+        if !vault.is_shutdown {
+            nav::check_fresh(vault, clock.unix_timestamp)?;
+        }
+        let total_assets = vault.redeemable_assets(ctx.accounts.vault_token_account.amount)?;
+        fees::accrue(vault, total_assets, clock.unix_timestamp)?;
+        let share_price = fees::share_price(total_assets, vault.share_supply()?)?;
+
+        // Calculate tokens to withdraw, rounding down in the vault's favour
+        let tokens_to_withdraw = convert_to_assets(shares, total_assets, vault.share_supply()?)?;
+        require!(tokens_to_withdraw > 0, VaultError::ZeroAssets);
+        require!(tokens_to_withdraw >= min_assets_out, VaultError::SlippageExceeded);
+
+        // Instant withdrawals are limited to the liquid buffer not owed to queued requests
+        require!(
+            tokens_to_withdraw <= vault.available_liquidity(ctx.accounts.vault_token_account.amount),
+            VaultError::InsufficientLiquidity
+        );
 
-        // Calculate tokens to withdraw
-        let tokens_to_withdraw = (shares as u128)
-            .checked_mul(vault.total_deposits as u128)
-            .unwrap()
-            .checked_div(vault.total_shares as u128)
-            .unwrap() as u64;
+        // Burn the redeemed share tokens
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.user_share_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::burn(cpi_ctx, shares)?;
 
         // Transfer tokens from vault to user
-        let seeds = &[
-            b"vault",
-            vault.authority.as_ref(),
-            &[vault.bump],
-        ];
+        let seeds = vault_signer_seeds!(vault);
         let signer = &[&seeds[..]];
 
         let cpi_accounts = Transfer {
@@ -100,21 +344,198 @@ pub mod chronos_vault {
         token::transfer(cpi_ctx, tokens_to_withdraw)?;
 
         // Update vault state
-        vault.total_deposits = vault.total_deposits.checked_sub(tokens_to_withdraw).unwrap();
         vault.total_shares = vault.total_shares.checked_sub(shares).unwrap();
 
-        // Update user position
-        user_position.shares = user_position.shares.checked_sub(shares).unwrap();
+        ctx.accounts.share_mint.reload()?;
+        require!(
+            ctx.accounts.share_mint.supply == vault.total_shares,
+            VaultError::ShareSupplyMismatch
+        );
 
+        let user_position = &mut ctx.accounts.user_position;
+        user_position.settle_fees(vault.fee_index)?;
+        let realized_pnl = user_position.record_withdrawal(shares, tokens_to_withdraw)?;
+
+        history::record(
+            &mut *ctx.accounts.history.load_mut()?,
+            vault,
+            total_assets
+                .checked_sub(tokens_to_withdraw)
+                .ok_or(VaultError::MathOverflow)?,
+            clock.unix_timestamp,
+        )?;
+
+        emit!(Withdrawn {
+            vault: vault.key(),
+            user: ctx.accounts.user.key(),
+            assets: tokens_to_withdraw,
+            shares,
+            share_price,
+            realized_pnl,
+            total_shares: vault.total_shares,
+            timestamp: clock.unix_timestamp,
+        });
         msg!("Withdrew {} tokens by burning {} shares", tokens_to_withdraw, shares);
         Ok(())
     }
 
-    /// Reserve a slot for guaranteed execution (integrates with Raiku)
+    /// Quote the shares a deposit of `amount` would mint at the current share price
+    pub fn preview_deposit(ctx: Context<Preview>, amount: u64) -> Result<u64> {
+        let mut vault = ctx.accounts.vault.clone().into_inner();
+        let total_assets = vault.total_assets(ctx.accounts.vault_token_account.amount)?;
+        fees::accrue(&mut vault, total_assets, Clock::get()?.unix_timestamp)?;
+        convert_to_shares(amount, total_assets, vault.share_supply()?)
+    }
+
+    /// Quote the tokens a withdrawal of `shares` would return at the current share price
+    pub fn preview_withdraw(ctx: Context<Preview>, shares: u64) -> Result<u64> {
+        let mut vault = ctx.accounts.vault.clone().into_inner();
+        let total_assets = vault.redeemable_assets(ctx.accounts.vault_token_account.amount)?;
+        fees::accrue(&mut vault, total_assets, Clock::get()?.unix_timestamp)?;
+        convert_to_assets(shares, total_assets, vault.share_supply()?)
+    }
+
+    /// Return, as return data, the share price growth in bps per year over at least the last
+    /// `window` seconds of the vault's share history (see `history::apy_bps`)
+    pub fn share_price_apy(ctx: Context<ReadShareHistory>, window: i64) -> Result<i64> {
+        let history = ctx.accounts.history.load()?;
+        let apy_bps = history::apy_bps(&history, window)?;
+
+        msg!("Share price APY over {}s: {} bps", window, apy_bps);
+        Ok(apy_bps)
+    }
+
+    /// Queue a withdrawal of `shares`, burning them now and locking the share price.
+    /// The assets become claimable once the next `execute_strategy` epoch processes the queue.
+    pub fn request_withdraw(
+        ctx: Context<RequestWithdraw>,
+        shares: u64,
+        min_assets_out: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+
+        // A shut-down vault is fully liquid, so exits go through `withdraw`
+        require!(!vault.is_shutdown, VaultError::VaultIsShutdown);
+        require!(!vault.withdrawals_paused, VaultError::WithdrawalsPaused);
+        require!(
+            ctx.accounts.user_share_account.amount >= shares,
+            VaultError::InsufficientShares
+        );
+
+        nav::check_fresh(vault, clock.unix_timestamp)?;
+        let total_assets = vault.total_assets(ctx.accounts.vault_token_account.amount)?;
+        fees::accrue(vault, total_assets, clock.unix_timestamp)?;
+        let share_price = fees::share_price(total_assets, vault.share_supply()?)?;
+
+        // Lock the payout at the current share price
+        let assets = convert_to_assets(shares, total_assets, vault.share_supply()?)?;
+        require!(assets > 0, VaultError::ZeroAssets);
+        require!(assets >= min_assets_out, VaultError::SlippageExceeded);
+
+        let cpi_accounts = Burn {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            from: ctx.accounts.user_share_account.to_account_info(),
+            authority: ctx.accounts.user.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        token::burn(cpi_ctx, shares)?;
+
+        vault.total_shares = vault.total_shares.checked_sub(shares).unwrap();
+        vault.queued_withdrawal_assets = vault.queued_withdrawal_assets.checked_add(assets).unwrap();
+
+        ctx.accounts.share_mint.reload()?;
+        require!(
+            ctx.accounts.share_mint.supply == vault.total_shares,
+            VaultError::ShareSupplyMismatch
+        );
+
+        let user_position = &mut ctx.accounts.user_position;
+        user_position.settle_fees(vault.fee_index)?;
+        let realized_pnl = user_position.record_withdrawal(shares, assets)?;
+
+        history::record(
+            &mut *ctx.accounts.history.load_mut()?,
+            vault,
+            vault.total_assets(ctx.accounts.vault_token_account.amount)?,
+            clock.unix_timestamp,
+        )?;
+
+        let request = &mut ctx.accounts.withdrawal_request;
+        request.vault = vault.key();
+        request.user = ctx.accounts.user.key();
+        request.shares = shares;
+        request.assets = assets;
+        request.epoch = vault.withdrawal_epoch;
+        request.requested_at = clock.unix_timestamp;
+        request.bump = ctx.bumps.withdrawal_request;
+
+        emit!(WithdrawalRequested {
+            vault: vault.key(),
+            user: request.user,
+            assets,
+            shares,
+            share_price,
+            realized_pnl,
+            epoch: request.epoch,
+            timestamp: clock.unix_timestamp,
+        });
+        msg!(
+            "Withdrawal of {} shares queued for {} tokens in epoch {}",
+            shares,
+            assets,
+            request.epoch
+        );
+        Ok(())
+    }
+
+    /// Pay out a withdrawal request once its epoch has been processed
+    pub fn claim_withdraw(ctx: Context<ClaimWithdraw>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let request = &ctx.accounts.withdrawal_request;
+
+        require!(!vault.withdrawals_paused, VaultError::WithdrawalsPaused);
+        require!(
+            request.epoch < vault.withdrawal_epoch,
+            VaultError::WithdrawalNotReady
+        );
+
+        let seeds = vault_signer_seeds!(vault);
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.vault_token_account.to_account_info(),
+            to: ctx.accounts.user_token_account.to_account_info(),
+            authority: vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::transfer(cpi_ctx, request.assets)?;
+
+        vault.claimable_withdrawal_assets = vault
+            .claimable_withdrawal_assets
+            .checked_sub(request.assets)
+            .unwrap();
+
+        emit!(WithdrawalClaimed {
+            vault: vault.key(),
+            user: request.user,
+            assets: request.assets,
+            epoch: request.epoch,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!("Claimed {} tokens from epoch {}", request.assets, request.epoch);
+        Ok(())
+    }
+
+    /// Reserve a slot for guaranteed execution through the orchestrator's Raiku integration,
+    /// with the vault PDA as the requester
     pub fn reserve_execution_slot(
         ctx: Context<ReserveSlot>,
         slot_time: i64,
-        reservation_type: ReservationType,
+        reservation_type: RaikuReservationType,
+        priority: u8,
     ) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
         let clock = Clock::get()?;
@@ -124,17 +545,43 @@ pub mod chronos_vault {
             slot_time > clock.unix_timestamp,
             VaultError::InvalidSlotTime
         );
+        require!(
+            vault.reserved_slots.len() < MAX_RESERVATIONS,
+            VaultError::ReservationsFull
+        );
+        require!(
+            !vault.reserved_slots.iter().any(|r| r.slot_time == slot_time),
+            VaultError::DuplicateReservation
+        );
 
-        // Create slot reservation
-        let reservation = SlotReservation {
+        // Create the reservation in the orchestrator
+        let seeds = vault_signer_seeds!(vault);
+        reservation::reserve(
+            ctx.accounts.orchestrator_program.to_account_info(),
+            ctx.accounts.orchestrator.to_account_info(),
+            ctx.accounts.reservation.to_account_info(),
+            vault.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            &[&seeds[..]],
             slot_time,
             reservation_type,
-            status: ReservationStatus::Pending,
-            reserved_at: clock.unix_timestamp,
-        };
+            priority,
+        )?;
 
-        vault.reserved_slots.push(reservation);
+        vault.reserved_slots.push(VaultReservation {
+            reservation: ctx.accounts.reservation.key(),
+            slot_time,
+        });
 
+        emit!(SlotReserved {
+            vault: vault.key(),
+            reservation: ctx.accounts.reservation.key(),
+            slot_time,
+            reservation_type,
+            priority,
+            timestamp: clock.unix_timestamp,
+        });
         msg!(
             "Reserved {:?} slot for execution at timestamp: {}",
             reservation_type,
@@ -143,163 +590,2884 @@ pub mod chronos_vault {
         Ok(())
     }
 
-    /// Execute vault strategy with guaranteed timing
-    pub fn execute_strategy(ctx: Context<ExecuteStrategy>) -> Result<()> {
+    /// Mark an active reservation as executed in the orchestrator and release it
+    pub fn mark_reservation_executed(ctx: Context<ManageReservation>) -> Result<()> {
         let vault = &mut ctx.accounts.vault;
-        let clock = Clock::get()?;
+        let index = vault.reservation_index(ctx.accounts.reservation.key())?;
 
-        // Check if rebalancing is needed
-        let time_since_last_rebalance = clock.unix_timestamp - vault.last_rebalance;
-        require!(
-            time_since_last_rebalance >= vault.rebalance_frequency,
-            VaultError::RebalanceTooSoon
-        );
+        let seeds = vault_signer_seeds!(vault);
+        reservation::mark_executed(
+            ctx.accounts.orchestrator_program.to_account_info(),
+            ctx.accounts.reservation.to_account_info(),
+            vault.to_account_info(),
+            &[&seeds[..]],
+        )?;
 
-        // Execute strategy based on type
-        match vault.strategy_type {
-            StrategyType::YieldOptimization => {
-                msg!("Executing yield optimization strategy");
-                // Strategy logic here (simulated for demo)
-            }
-            StrategyType::DeltaNeutral => {
-                msg!("Executing delta-neutral strategy");
-                // Strategy logic here (simulated for demo)
-            }
-            StrategyType::Arbitrage => {
-                msg!("Executing arbitrage strategy");
-                // Strategy logic here (simulated for demo)
+        let released = vault.reserved_slots.remove(index);
+        emit!(ReservationReleased {
+            vault: vault.key(),
+            reservation: released.reservation,
+            slot_time: released.slot_time,
+            outcome: ReservationOutcome::Executed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!("Reservation at {} executed", released.slot_time);
+        Ok(())
+    }
+
+    /// Mark an active reservation as failed in the orchestrator and release it
+    pub fn mark_reservation_failed(ctx: Context<ManageReservation>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let index = vault.reservation_index(ctx.accounts.reservation.key())?;
+
+        let seeds = vault_signer_seeds!(vault);
+        reservation::mark_failed(
+            ctx.accounts.orchestrator_program.to_account_info(),
+            ctx.accounts.reservation.to_account_info(),
+            vault.to_account_info(),
+            &[&seeds[..]],
+        )?;
+
+        let released = vault.reserved_slots.remove(index);
+        emit!(ReservationReleased {
+            vault: vault.key(),
+            reservation: released.reservation,
+            slot_time: released.slot_time,
+            outcome: ReservationOutcome::Failed,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!("Reservation at {} failed", released.slot_time);
+        Ok(())
+    }
+
+    /// Cancel an active reservation, closing it in the orchestrator
+    pub fn cancel_reservation(ctx: Context<ManageReservation>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let index = vault.reservation_index(ctx.accounts.reservation.key())?;
+
+        let seeds = vault_signer_seeds!(vault);
+        reservation::cancel(
+            ctx.accounts.orchestrator_program.to_account_info(),
+            ctx.accounts.reservation.to_account_info(),
+            vault.to_account_info(),
+            ctx.accounts.authority.to_account_info(),
+            &[&seeds[..]],
+        )?;
+
+        let released = vault.reserved_slots.remove(index);
+        emit!(ReservationReleased {
+            vault: vault.key(),
+            reservation: released.reservation,
+            slot_time: released.slot_time,
+            outcome: ReservationOutcome::Cancelled,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!("Reservation at {} cancelled", released.slot_time);
+        Ok(())
+    }
+
+    /// Mark active reservations whose window has passed as failed (permissionless).
+    /// Remaining accounts: the orchestrator reservation accounts to check.
+    pub fn record_missed_reservations<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SweepReservations<'info>>,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let now = Clock::get()?.unix_timestamp;
+
+        let creator = vault.creator;
+        let vault_id = vault.vault_id.to_le_bytes();
+        let bump = [vault.bump];
+        let seeds: &[&[u8]] = &[b"vault", creator.as_ref(), &vault_id, &bump];
+
+        let mut missed = 0;
+        for info in ctx.remaining_accounts.iter() {
+            let reservation = Account::<OrchestratorReservation>::try_from(info)?;
+            let index = vault.reservation_index(info.key())?;
+
+            if reservation.status.is_active() && reservation.slot_time + vault.slot_tolerance < now {
+                reservation::mark_failed(
+                    ctx.accounts.orchestrator_program.to_account_info(),
+                    info.clone(),
+                    vault.to_account_info(),
+                    &[seeds],
+                )?;
+                let released = vault.reserved_slots.remove(index);
+                emit!(ReservationReleased {
+                    vault: vault.key(),
+                    reservation: released.reservation,
+                    slot_time: released.slot_time,
+                    outcome: ReservationOutcome::Missed,
+                    timestamp: now,
+                });
+                missed += 1;
             }
         }
 
-        vault.last_rebalance = clock.unix_timestamp;
-        msg!("Strategy executed successfully");
+        msg!("Recorded {} missed reservations", missed);
         Ok(())
     }
-}
 
-// Account Structures
+    /// Release reservations that are no longer active or were closed (permissionless).
+    /// Remaining accounts: the orchestrator reservation accounts to check.
+    pub fn prune_reservations<'info>(
+        ctx: Context<'_, '_, 'info, 'info, SweepReservations<'info>>,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let now = Clock::get()?.unix_timestamp;
 
-#[derive(Accounts)]
-pub struct InitializeVault<'info> {
-    #[account(
-        init,
-        payer = authority,
-        space = 8 + Vault::INIT_SPACE,
-        seeds = [b"vault", authority.key().as_ref()],
-        bump
-    )]
-    pub vault: Account<'info, Vault>,
-    
-    #[account(mut)]
-    pub authority: Signer<'info>,
-    
-    pub system_program: Program<'info, System>,
-}
+        let before = vault.reserved_slots.len();
+        for info in ctx.remaining_accounts.iter() {
+            let index = vault.reservation_index(info.key())?;
+            let finished = info.data_is_empty()
+                || !Account::<OrchestratorReservation>::try_from(info)?
+                    .status
+                    .is_active();
+            if finished {
+                let released = vault.reserved_slots.remove(index);
+                emit!(ReservationReleased {
+                    vault: vault.key(),
+                    reservation: released.reservation,
+                    slot_time: released.slot_time,
+                    outcome: ReservationOutcome::Pruned,
+                    timestamp: now,
+                });
+            }
+        }
 
-#[derive(Accounts)]
-pub struct Deposit<'info> {
-    #[account(
-        mut,
-        seeds = [b"vault", vault.authority.as_ref()],
-        bump = vault.bump
-    )]
-    pub vault: Account<'info, Vault>,
-    
-    #[account(
-        init_if_needed,
-        payer = user,
-        space = 8 + UserPosition::INIT_SPACE,
-        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
-        bump
-    )]
-    pub user_position: Account<'info, UserPosition>,
-    
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-    pub system_program: Program<'info, System>,
-}
+        msg!("Pruned {} reservations", before - vault.reserved_slots.len());
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct Withdraw<'info> {
-    #[account(
-        mut,
-        seeds = [b"vault", vault.authority.as_ref()],
-        bump = vault.bump
-    )]
-    pub vault: Account<'info, Vault>,
-    
-    #[account(
-        mut,
-        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
-        bump
-    )]
-    pub user_position: Account<'info, UserPosition>,
-    
-    #[account(mut)]
-    pub user: Signer<'info>,
-    
-    #[account(mut)]
-    pub user_token_account: Account<'info, TokenAccount>,
-    
-    #[account(mut)]
-    pub vault_token_account: Account<'info, TokenAccount>,
-    
-    pub token_program: Program<'info, Token>,
-}
+    /// Propose a new vault authority, who must accept before the handover takes effect.
+    /// Proposing `None` cancels a pending handover.
+    pub fn propose_authority(
+        ctx: Context<UpdateVault>,
+        new_authority: Option<Pubkey>,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.pending_authority = new_authority;
 
-#[derive(Accounts)]
-pub struct ReserveSlot<'info> {
-    #[account(
-        mut,
-        seeds = [b"vault", vault.authority.as_ref()],
-        bump = vault.bump,
-        has_one = authority
-    )]
-    pub vault: Account<'info, Vault>,
-    
-    pub authority: Signer<'info>,
-}
+        emit!(AuthorityTransferProposed {
+            vault: vault.key(),
+            authority: vault.authority,
+            pending_authority: new_authority,
+        });
+        msg!("Pending authority set to {:?}", new_authority);
+        Ok(())
+    }
 
-#[derive(Accounts)]
-pub struct ExecuteStrategy<'info> {
-    #[account(
-        mut,
-        seeds = [b"vault", vault.authority.as_ref()],
-        bump = vault.bump
-    )]
-    pub vault: Account<'info, Vault>,
-}
+    /// Accept a proposed authority handover as the pending authority
+    pub fn accept_authority(ctx: Context<AcceptAuthority>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let previous_authority = vault.authority;
 
-// Data Structures
+        vault.authority = ctx.accounts.new_authority.key();
+        vault.pending_authority = None;
+
+        emit!(AuthorityTransferred {
+            vault: vault.key(),
+            previous_authority,
+            new_authority: vault.authority,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!(
+            "Vault authority transferred from {} to {}",
+            previous_authority,
+            vault.authority
+        );
+        Ok(())
+    }
+
+    /// Update the rebalance frequency immediately and propose a strategy type or risk level
+    /// change, which applies after `STRATEGY_CHANGE_TIMELOCK`
+    pub fn update_vault_config(
+        ctx: Context<UpdateVault>,
+        rebalance_frequency: Option<i64>,
+        strategy_type: Option<StrategyType>,
+        risk_level: Option<u8>,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+
+        if let Some(rebalance_frequency) = rebalance_frequency {
+            check_rebalance_frequency(rebalance_frequency)?;
+            vault.rebalance_frequency = rebalance_frequency;
+            msg!("Rebalance frequency set to {}s", rebalance_frequency);
+        }
+
+        if strategy_type.is_some() || risk_level.is_some() {
+            let strategy_type = strategy_type.unwrap_or(vault.strategy_type);
+            let risk_level = risk_level.unwrap_or(vault.risk_level);
+            risk::check_strategy(risk_level, strategy_type)?;
+
+            let effective_at = clock.unix_timestamp + STRATEGY_CHANGE_TIMELOCK;
+            vault.pending_strategy_change = Some(PendingStrategyChange {
+                strategy_type,
+                risk_level,
+                effective_at,
+            });
+
+            emit!(StrategyChangeProposed {
+                vault: vault.key(),
+                strategy_type,
+                risk_level,
+                effective_at,
+            });
+            msg!(
+                "Strategy change proposed: {:?} at risk level {} (effective at {})",
+                strategy_type,
+                risk_level,
+                effective_at
+            );
+        }
+
+        emit!(VaultConfigUpdated {
+            vault: vault.key(),
+            rebalance_frequency: vault.rebalance_frequency,
+            timestamp: clock.unix_timestamp,
+        });
+        Ok(())
+    }
+
+    /// Apply a proposed strategy change after its timelock (permissionless)
+    pub fn apply_strategy_change(ctx: Context<ApplyStrategyChange>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+
+        let change = vault
+            .pending_strategy_change
+            .ok_or(VaultError::NoPendingStrategyChange)?;
+        require!(
+            clock.unix_timestamp >= change.effective_at,
+            VaultError::StrategyChangeTimelocked
+        );
+        risk::check_strategy(change.risk_level, change.strategy_type)?;
+        require!(
+            change.strategy_type == StrategyType::Allocator || vault.children.is_empty(),
+            VaultError::ChildVaultsRegistered
+        );
+
+        vault.strategy_type = change.strategy_type;
+        vault.risk_level = change.risk_level;
+        vault.pending_strategy_change = None;
+        ctx.accounts.registry_entry.strategy_type = change.strategy_type;
+
+        emit!(StrategyChangeApplied {
+            vault: vault.key(),
+            strategy_type: change.strategy_type,
+            risk_level: change.risk_level,
+            timestamp: clock.unix_timestamp,
+        });
+        msg!(
+            "Strategy change applied: {:?} at risk level {}",
+            change.strategy_type,
+            change.risk_level
+        );
+        Ok(())
+    }
+
+    /// Choose between interval-based execution and execution only inside confirmed
+    /// reservation windows of `slot_tolerance` seconds either side of the slot time
+    pub fn set_execution_mode(
+        ctx: Context<UpdateVault>,
+        execution_mode: ExecutionMode,
+        slot_tolerance: i64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        require!(
+            slot_tolerance > 0 && slot_tolerance <= MAX_SLOT_TOLERANCE,
+            VaultError::InvalidSlotTolerance
+        );
+
+        vault.execution_mode = execution_mode;
+        vault.slot_tolerance = slot_tolerance;
+
+        emit!(ExecutionModeUpdated {
+            vault: vault.key(),
+            execution_mode,
+            slot_tolerance,
+        });
+        msg!(
+            "Execution mode set to {:?} with slot tolerance {}s",
+            execution_mode,
+            slot_tolerance
+        );
+        Ok(())
+    }
+
+    /// Set when rebalances fall due in Interval mode and whether executions reserve the next slot
+    pub fn set_rebalance_schedule(
+        ctx: Context<UpdateVault>,
+        schedule: RebalanceSchedule,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        schedule::check_schedule(schedule.kind, schedule.deviation_trigger_bps)?;
+        vault.schedule = schedule;
+        let next_due = schedule::next_due(vault);
+
+        emit!(ScheduleUpdated {
+            vault: vault.key(),
+            kind: schedule.kind,
+            deviation_trigger_bps: schedule.deviation_trigger_bps,
+            auto_reserve: schedule.auto_reserve.is_some(),
+            next_due,
+        });
+        msg!(
+            "Rebalance schedule set to {:?}, next due at {}",
+            schedule.kind,
+            next_due
+        );
+        Ok(())
+    }
+
+    /// Configure the vault-wide TVL cap, the per-user cap and the allowlist gate.
+    /// A cap of zero means no limit.
+    pub fn set_deposit_limits(
+        ctx: Context<UpdateVault>,
+        deposit_cap: u64,
+        user_deposit_cap: u64,
+        allowlist_enabled: bool,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        vault.deposit_cap = deposit_cap;
+        vault.user_deposit_cap = user_deposit_cap;
+        vault.allowlist_enabled = allowlist_enabled;
+
+        emit!(DepositLimitsUpdated {
+            vault: vault.key(),
+            deposit_cap,
+            user_deposit_cap,
+            allowlist_enabled,
+        });
+        msg!(
+            "Deposit limits set: cap {}, per-user cap {}, allowlist {}",
+            deposit_cap,
+            user_deposit_cap,
+            allowlist_enabled
+        );
+        Ok(())
+    }
+
+    /// Allow `user` to deposit while the allowlist gate is enabled
+    pub fn add_to_allowlist(ctx: Context<AddToAllowlist>, user: Pubkey) -> Result<()> {
+        let entry = &mut ctx.accounts.allowlist_entry;
+        entry.vault = ctx.accounts.vault.key();
+        entry.user = user;
+        entry.bump = ctx.bumps.allowlist_entry;
+
+        emit!(AllowlistUpdated {
+            vault: entry.vault,
+            user,
+            allowed: true,
+        });
+        msg!("User {} added to the allowlist", user);
+        Ok(())
+    }
+
+    /// Revoke `user`'s allowlist entry, returning its rent to the authority
+    pub fn remove_from_allowlist(ctx: Context<RemoveFromAllowlist>, user: Pubkey) -> Result<()> {
+        emit!(AllowlistUpdated {
+            vault: ctx.accounts.vault.key(),
+            user,
+            allowed: false,
+        });
+        msg!("User {} removed from the allowlist", user);
+        Ok(())
+    }
+
+    /// Register a strategy adapter program, which must implement the `adapter` CPI interface,
+    /// and the position it keeps for this vault
+    pub fn add_adapter(
+        ctx: Context<UpdateVault>,
+        program_id: Pubkey,
+        position: Pubkey,
+        weight: u16,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        require!(vault.adapters.len() < MAX_ADAPTERS, VaultError::TooManyAdapters);
+        require!(
+            !vault.adapters.iter().any(|a| a.program_id == program_id),
+            VaultError::AdapterAlreadyRegistered
+        );
+
+        vault.adapters.push(AdapterAllocation {
+            program_id,
+            position,
+            weight,
+            deployed: 0,
+        });
+
+        emit!(AdapterUpdated {
+            vault: vault.key(),
+            program_id,
+            weight,
+            removed: false,
+        });
+        msg!("Adapter {} registered with weight {}", program_id, weight);
+        Ok(())
+    }
+
+    /// Change the target weight of a registered adapter
+    pub fn set_adapter_weight(
+        ctx: Context<UpdateVault>,
+        program_id: Pubkey,
+        weight: u16,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        let allocation = vault
+            .adapters
+            .iter_mut()
+            .find(|a| a.program_id == program_id)
+            .ok_or(VaultError::AdapterNotFound)?;
+        allocation.weight = weight;
+
+        emit!(AdapterUpdated {
+            vault: vault.key(),
+            program_id,
+            weight,
+            removed: false,
+        });
+        msg!("Adapter {} weight set to {}", program_id, weight);
+        Ok(())
+    }
+
+    /// Remove an adapter that no longer holds any vault assets
+    pub fn remove_adapter(ctx: Context<UpdateVault>, program_id: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        let index = vault
+            .adapters
+            .iter()
+            .position(|a| a.program_id == program_id)
+            .ok_or(VaultError::AdapterNotFound)?;
+        require!(
+            vault.adapters[index].deployed == 0,
+            VaultError::AdapterHasDeployedAssets
+        );
+        vault.adapters.remove(index);
+
+        emit!(AdapterUpdated {
+            vault: vault.key(),
+            program_id,
+            weight: 0,
+            removed: true,
+        });
+        msg!("Adapter {} removed", program_id);
+        Ok(())
+    }
+
+    /// Register a child vault for an allocator vault to invest in, opening the allocator's
+    /// share account and position in the child
+    pub fn add_child_vault(ctx: Context<AddChildVault>, weight: u16) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let child = &ctx.accounts.child_vault;
+
+        require!(
+            vault.strategy_type == StrategyType::Allocator,
+            VaultError::NotAllocatorVault
+        );
+        require!(
+            vault.children.len() < MAX_CHILD_VAULTS,
+            VaultError::TooManyChildVaults
+        );
+        require!(
+            !vault.children.iter().any(|c| c.vault == child.key()),
+            VaultError::ChildVaultAlreadyRegistered
+        );
+        // Allocators do not nest, and children must take the allocator's asset without an allowlist
+        require!(
+            child.key() != vault.key()
+                && child.strategy_type != StrategyType::Allocator
+                && child.asset_mint == vault.asset_mint
+                && !child.allowlist_enabled,
+            VaultError::InvalidChildVault
+        );
+
+        vault.children.push(ChildVault {
+            vault: child.key(),
+            share_account: ctx.accounts.child_share_account.key(),
+            weight,
+            last_value: 0,
+        });
+
+        emit!(ChildVaultUpdated {
+            vault: vault.key(),
+            child: child.key(),
+            weight,
+            removed: false,
+        });
+        msg!("Child vault {} registered with weight {}", child.key(), weight);
+        Ok(())
+    }
+
+    /// Change the target weight of a registered child vault
+    pub fn set_child_weight(ctx: Context<UpdateVault>, child: Pubkey, weight: u16) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        let registered = vault
+            .children
+            .iter_mut()
+            .find(|c| c.vault == child)
+            .ok_or(VaultError::ChildVaultNotFound)?;
+        registered.weight = weight;
+
+        emit!(ChildVaultUpdated {
+            vault: vault.key(),
+            child,
+            weight,
+            removed: false,
+        });
+        msg!("Child vault {} weight set to {}", child, weight);
+        Ok(())
+    }
+
+    /// Remove a child vault whose last valuation was zero
+    pub fn remove_child_vault(ctx: Context<UpdateVault>, child: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        let index = vault
+            .children
+            .iter()
+            .position(|c| c.vault == child)
+            .ok_or(VaultError::ChildVaultNotFound)?;
+        require!(
+            vault.children[index].last_value == 0,
+            VaultError::ChildVaultNotEmpty
+        );
+        vault.children.remove(index);
+
+        emit!(ChildVaultUpdated {
+            vault: vault.key(),
+            child,
+            weight: 0,
+            removed: true,
+        });
+        msg!("Child vault {} removed", child);
+        Ok(())
+    }
+
+    /// Register a non-asset token the vault holds, valued through a Pyth-style price account
+    pub fn add_holding(ctx: Context<AddHolding>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let mint = ctx.accounts.holding_mint.key();
+
+        require!(vault.holdings.len() < MAX_HOLDINGS, VaultError::TooManyHoldings);
+        require!(
+            mint != vault.asset_mint && !vault.holdings.iter().any(|h| h.mint == mint),
+            VaultError::HoldingAlreadyRegistered
+        );
+
+        // Reject oracles that cannot currently be read
+        nav::read_price(&ctx.accounts.oracle, Clock::get()?.unix_timestamp)?;
+
+        vault.holdings.push(Holding {
+            mint,
+            token_account: ctx.accounts.holding_token_account.key(),
+            oracle: ctx.accounts.oracle.key(),
+            decimals: ctx.accounts.holding_mint.decimals,
+            last_value: 0,
+            hedge_market: None,
+        });
+
+        emit!(HoldingAdded {
+            vault: vault.key(),
+            mint,
+            token_account: ctx.accounts.holding_token_account.key(),
+            oracle: ctx.accounts.oracle.key(),
+        });
+        msg!("Holding {} registered with oracle {}", mint, ctx.accounts.oracle.key());
+        Ok(())
+    }
+
+    /// Remove a holding whose last valuation was zero
+    pub fn remove_holding(ctx: Context<UpdateVault>, mint: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        let index = vault
+            .holdings
+            .iter()
+            .position(|h| h.mint == mint)
+            .ok_or(VaultError::HoldingNotFound)?;
+        require!(
+            vault.holdings[index].last_value == 0,
+            VaultError::HoldingNotEmpty
+        );
+        require!(
+            !vault.hedge_orders.iter().any(|o| o.holding_mint == mint),
+            VaultError::HedgeOrderOpen
+        );
+        require!(
+            !matches!(vault.arbitrage, Some(a) if a.holding_mint == mint),
+            VaultError::HoldingUsedForArbitrage
+        );
+        vault.holdings.remove(index);
+
+        emit!(HoldingRemoved {
+            vault: vault.key(),
+            mint,
+        });
+        msg!("Holding {} removed", mint);
+        Ok(())
+    }
+
+    /// Set the chronos_dex market that delta-neutral executions hedge a holding on, or clear it
+    /// by omitting the market. The market must trade the holding against the vault asset.
+    pub fn set_hedge_market(ctx: Context<SetHedgeMarket>, mint: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        let index = vault
+            .holdings
+            .iter()
+            .position(|h| h.mint == mint)
+            .ok_or(VaultError::HoldingNotFound)?;
+        require!(
+            !vault.hedge_orders.iter().any(|o| o.holding_mint == mint),
+            VaultError::HedgeOrderOpen
+        );
+
+        let market = match &ctx.accounts.market {
+            Some(market) => {
+                require!(
+                    market.base_mint == mint && market.quote_mint == vault.asset_mint,
+                    VaultError::InvalidHedgeMarket
+                );
+                Some(market.key())
+            }
+            None => None,
+        };
+        vault.holdings[index].hedge_market = market;
+
+        emit!(HedgeMarketUpdated {
+            vault: vault.key(),
+            holding_mint: mint,
+            market,
+        });
+        msg!("Hedge market for {} set to {:?}", mint, market);
+        Ok(())
+    }
+
+    /// Set the pair of chronos_dex markets Arbitrage vaults trade a holding between, with the
+    /// largest base amount per trade and the profit floor below which a trade reverts, or
+    /// clear the route by omitting both markets
+    pub fn set_arbitrage_config(
+        ctx: Context<SetArbitrageConfig>,
+        max_trade_amount: u64,
+        min_profit: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        vault.arbitrage = match (&ctx.accounts.market_a, &ctx.accounts.market_b) {
+            (Some(market_a), Some(market_b)) => {
+                let holding_mint = market_a.base_mint;
+                require!(
+                    market_a.key() != market_b.key()
+                        && market_b.base_mint == holding_mint
+                        && market_a.quote_mint == vault.asset_mint
+                        && market_b.quote_mint == vault.asset_mint,
+                    VaultError::InvalidArbitrageConfig
+                );
+                require!(
+                    vault.holdings.iter().any(|h| h.mint == holding_mint),
+                    VaultError::HoldingNotFound
+                );
+                // A positive floor means a completed arbitrage can never lose the vault money
+                require!(
+                    max_trade_amount > 0 && min_profit > 0,
+                    VaultError::InvalidArbitrageConfig
+                );
+                Some(ArbitrageConfig {
+                    holding_mint,
+                    markets: [market_a.key(), market_b.key()],
+                    max_trade_amount,
+                    min_profit,
+                })
+            }
+            (None, None) => None,
+            _ => return err!(VaultError::InvalidArbitrageConfig),
+        };
+
+        emit!(ArbitrageConfigUpdated {
+            vault: vault.key(),
+            markets: vault.arbitrage.map(|a| a.markets),
+            max_trade_amount,
+            min_profit,
+        });
+        msg!("Arbitrage route set to {:?}", vault.arbitrage.map(|a| a.markets));
+        Ok(())
+    }
+
+    /// Stop tracking a holding's hedge order, cancelling it on the DEX if it is still open.
+    /// Partially filled orders cannot be cancelled on the DEX, so they stay tracked.
+    pub fn cancel_hedge_order(ctx: Context<CancelHedgeOrder>, mint: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let order = &ctx.accounts.order;
+
+        let index = vault
+            .hedge_orders
+            .iter()
+            .position(|o| o.holding_mint == mint && o.order == order.key())
+            .ok_or(VaultError::HedgeOrderNotFound)?;
+        require!(
+            order.status != OrderStatus::PartiallyFilled,
+            VaultError::HedgeOrderPartiallyFilled
+        );
+
+        if order.status == OrderStatus::Open {
+            let seeds = vault_signer_seeds!(vault);
+            dex::cancel_order(
+                ctx.accounts.dex_program.to_account_info(),
+                order.to_account_info(),
+                vault.to_account_info(),
+                &[&seeds[..]],
+            )?;
+        }
+        let cancelled = vault.hedge_orders.remove(index);
+
+        emit!(HedgeUpdated {
+            vault: vault.key(),
+            holding_mint: mint,
+            market: cancelled.market,
+            order: None,
+            cancelled_order: Some(cancelled.order),
+            amount: 0,
+            price: cancelled.price,
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!("Hedge order {} for {} cancelled", cancelled.order, mint);
+        Ok(())
+    }
+
+    /// Offer the full balance of a shut-down vault's holding for sale at its oracle price on
+    /// `market`, tracked as the holding's hedge order so that counterparties fill it through
+    /// `settle_hedge`. A holding with a tracked order keeps selling through that order instead.
+    pub fn unwind_holding(ctx: Context<UnwindHolding>, mint: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        require!(vault.is_shutdown, VaultError::VaultNotShutdown);
+
+        let holding = *vault
+            .holdings
+            .iter()
+            .find(|h| h.mint == mint)
+            .ok_or(VaultError::HoldingNotFound)?;
+        require_keys_eq!(
+            ctx.accounts.holding_token_account.key(),
+            holding.token_account,
+            VaultError::InvalidHoldingAccounts
+        );
+        require_keys_eq!(
+            ctx.accounts.oracle.key(),
+            holding.oracle,
+            VaultError::InvalidHoldingAccounts
+        );
+        require!(
+            !vault.hedge_orders.iter().any(|o| o.holding_mint == mint),
+            VaultError::HedgeOrderOpen
+        );
+        let market = &ctx.accounts.market;
+        require!(
+            market.base_mint == mint && market.quote_mint == vault.asset_mint,
+            VaultError::InvalidHedgeMarket
+        );
+        let amount = ctx.accounts.holding_token_account.amount;
+        require!(amount > 0, VaultError::NothingToUnwind);
+
+        let now = Clock::get()?.unix_timestamp;
+        let oracle_price = nav::read_price(&ctx.accounts.oracle, now)?;
+        let price = nav::holding_value(
+            dex::PRICE_SCALE,
+            &oracle_price,
+            holding.decimals,
+            vault.asset_decimals,
+        )?;
+
+        let seeds = vault_signer_seeds!(vault);
+        dex::place_order(
+            ctx.accounts.dex_program.to_account_info(),
+            market.to_account_info(),
+            ctx.accounts.order.to_account_info(),
+            vault.to_account_info(),
+            ctx.accounts.signer.to_account_info(),
+            ctx.accounts.system_program.to_account_info(),
+            &[&seeds[..]],
+            OrderSide::Sell,
+            price,
+            amount,
+            now,
+        )?;
+        vault.hedge_orders.push(HedgeOrder {
+            holding_mint: mint,
+            market: market.key(),
+            order: ctx.accounts.order.key(),
+            side: OrderSide::Sell,
+            price,
+            amount,
+            batch_id: market.current_batch_id,
+        });
+
+        emit!(HedgeUpdated {
+            vault: vault.key(),
+            holding_mint: mint,
+            market: market.key(),
+            order: Some(ctx.accounts.order.key()),
+            cancelled_order: None,
+            amount,
+            price,
+            timestamp: now,
+        });
+        msg!("Unwinding {} of {} at {}", amount, mint, price);
+        Ok(())
+    }
+
+    /// Fill `amount` of a holding's tracked hedge order against a counterparty's crossing buy
+    /// order at `price`, no lower than the holding's oracle price. The vault signs for its side;
+    /// the counterparty signs for theirs.
+    pub fn settle_hedge(
+        ctx: Context<SettleHedge>,
+        mint: Pubkey,
+        amount: u64,
+        price: u64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        // A shut-down vault keeps settling so that its holdings can be sold off
+        require!(
+            !vault.execution_paused || vault.is_shutdown,
+            VaultError::ExecutionPaused
+        );
+
+        let order_key = ctx.accounts.hedge_order.key();
+        let tracked_index = vault
+            .hedge_orders
+            .iter()
+            .position(|o| o.holding_mint == mint && o.order == order_key)
+            .ok_or(VaultError::HedgeOrderNotFound)?;
+        let holding_index = vault
+            .holdings
+            .iter()
+            .position(|h| h.mint == mint)
+            .ok_or(VaultError::HoldingNotFound)?;
+        let holding = vault.holdings[holding_index];
+        require_keys_eq!(
+            ctx.accounts.holding_token_account.key(),
+            holding.token_account,
+            VaultError::InvalidHoldingAccounts
+        );
+        require_keys_eq!(
+            ctx.accounts.oracle.key(),
+            holding.oracle,
+            VaultError::InvalidHoldingAccounts
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let oracle_price = nav::read_price(&ctx.accounts.oracle, now)?;
+        let reference_price = nav::holding_value(
+            dex::PRICE_SCALE,
+            &oracle_price,
+            holding.decimals,
+            vault.asset_decimals,
+        )?;
+        require!(price >= reference_price, VaultError::HedgePriceBelowOracle);
+
+        let seeds = vault_signer_seeds!(vault);
+        dex::settle_trade(
+            ctx.accounts.dex_program.to_account_info(),
+            SettleTrade {
+                market: ctx.accounts.market.to_account_info(),
+                buy_order: ctx.accounts.counterparty_order.to_account_info(),
+                sell_order: ctx.accounts.hedge_order.to_account_info(),
+                buyer: ctx.accounts.counterparty.to_account_info(),
+                seller: vault.to_account_info(),
+                buyer_base_account: ctx.accounts.counterparty_base_account.to_account_info(),
+                buyer_quote_account: ctx.accounts.counterparty_quote_account.to_account_info(),
+                seller_base_account: ctx.accounts.holding_token_account.to_account_info(),
+                seller_quote_account: ctx.accounts.vault_token_account.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+            &[&seeds[..]],
+            amount,
+            price,
+        )?;
+
+        // Track only the unfilled part of the hedge, so that leverage counts what is still short
+        ctx.accounts.hedge_order.reload()?;
+        let order = &ctx.accounts.hedge_order;
+        let remaining = order.amount.saturating_sub(order.filled_amount);
+        if remaining == 0 {
+            vault.hedge_orders.remove(tracked_index);
+        } else {
+            vault.hedge_orders[tracked_index].amount = remaining;
+        }
+
+        // Re-value the holding at its reduced balance
+        ctx.accounts.holding_token_account.reload()?;
+        nav::revalue_holding(
+            vault,
+            holding_index,
+            ctx.accounts.holding_token_account.amount,
+            &oracle_price,
+        )?;
+
+        emit!(HedgeSettled {
+            vault: vault.key(),
+            holding_mint: mint,
+            market: ctx.accounts.market.key(),
+            order: order_key,
+            amount,
+            price,
+            remaining,
+            timestamp: now,
+        });
+        msg!("Hedge order {} filled {} at {}", order_key, amount, price);
+        Ok(())
+    }
+
+    /// Fill `amount` of one of the vault's arbitrage orders against a counterparty's crossing
+    /// order at `price`, no worse than the holding's oracle price: at or below it when the vault
+    /// buys, at or above it when the vault sells. The counterparty submits and signs for their
+    /// side; the vault signs for its own.
+    pub fn settle_arbitrage(ctx: Context<SettleArbitrage>, amount: u64, price: u64) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        // A shut-down vault keeps settling so that its holdings can be sold off
+        require!(
+            !vault.execution_paused || vault.is_shutdown,
+            VaultError::ExecutionPaused
+        );
+
+        let config = vault.arbitrage.ok_or(VaultError::InvalidArbitrageConfig)?;
+        let market = ctx.accounts.market.key();
+        require!(
+            config.markets.contains(&market),
+            VaultError::InvalidArbitrageAccounts
+        );
+        let holding_index = vault
+            .holdings
+            .iter()
+            .position(|h| h.mint == config.holding_mint)
+            .ok_or(VaultError::HoldingNotFound)?;
+        let holding = vault.holdings[holding_index];
+        require_keys_eq!(
+            ctx.accounts.holding_token_account.key(),
+            holding.token_account,
+            VaultError::InvalidHoldingAccounts
+        );
+        require_keys_eq!(
+            ctx.accounts.oracle.key(),
+            holding.oracle,
+            VaultError::InvalidHoldingAccounts
+        );
+
+        let now = Clock::get()?.unix_timestamp;
+        let oracle_price = nav::read_price(&ctx.accounts.oracle, now)?;
+        let reference_price = nav::holding_value(
+            dex::PRICE_SCALE,
+            &oracle_price,
+            holding.decimals,
+            vault.asset_decimals,
+        )?;
+        let vault_buys = ctx.accounts.vault_order.side == OrderSide::Buy;
+        require!(
+            if vault_buys {
+                price <= reference_price
+            } else {
+                price >= reference_price
+            },
+            VaultError::ArbitragePriceOffOracle
+        );
+
+        let seeds = vault_signer_seeds!(vault);
+        let vault_info = vault.to_account_info();
+        let vault_order = ctx.accounts.vault_order.to_account_info();
+        let counterparty_order = ctx.accounts.counterparty_order.to_account_info();
+        let counterparty = ctx.accounts.counterparty.to_account_info();
+        let holding_token_account = ctx.accounts.holding_token_account.to_account_info();
+        let vault_token_account = ctx.accounts.vault_token_account.to_account_info();
+        let counterparty_base_account = ctx.accounts.counterparty_base_account.to_account_info();
+        let counterparty_quote_account = ctx.accounts.counterparty_quote_account.to_account_info();
+        let accounts = if vault_buys {
+            SettleTrade {
+                market: ctx.accounts.market.to_account_info(),
+                buy_order: vault_order,
+                sell_order: counterparty_order,
+                buyer: vault_info,
+                seller: counterparty,
+                buyer_base_account: holding_token_account,
+                buyer_quote_account: vault_token_account,
+                seller_base_account: counterparty_base_account,
+                seller_quote_account: counterparty_quote_account,
+                token_program: ctx.accounts.token_program.to_account_info(),
+            }
+        } else {
+            SettleTrade {
+                market: ctx.accounts.market.to_account_info(),
+                buy_order: counterparty_order,
+                sell_order: vault_order,
+                buyer: counterparty,
+                seller: vault_info,
+                buyer_base_account: counterparty_base_account,
+                buyer_quote_account: counterparty_quote_account,
+                seller_base_account: holding_token_account,
+                seller_quote_account: vault_token_account,
+                token_program: ctx.accounts.token_program.to_account_info(),
+            }
+        };
+        dex::settle_trade(
+            ctx.accounts.dex_program.to_account_info(),
+            accounts,
+            &[&seeds[..]],
+            amount,
+            price,
+        )?;
+
+        // Re-value the holding at its new balance
+        ctx.accounts.holding_token_account.reload()?;
+        nav::revalue_holding(
+            vault,
+            holding_index,
+            ctx.accounts.holding_token_account.amount,
+            &oracle_price,
+        )?;
+
+        emit!(ArbitrageSettled {
+            vault: vault.key(),
+            market,
+            order: ctx.accounts.vault_order.key(),
+            vault_buys,
+            amount,
+            price,
+            timestamp: now,
+        });
+        msg!(
+            "Arbitrage order {} filled {} at {}",
+            ctx.accounts.vault_order.key(),
+            amount,
+            price
+        );
+        Ok(())
+    }
+
+    /// Re-value the vault's holdings from their oracles and its child vault positions from
+    /// the children's share prices (permissionless).
+    /// Remaining accounts: for each registered child vault, in registry order, the accounts
+    /// listed on `allocator::ChildAccounts`; then for each registered holding, in registry
+    /// order, its token account and price account.
+    pub fn update_nav<'info>(ctx: Context<'_, '_, 'info, 'info, UpdateNav<'info>>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+
+        let child_count =
+            (vault.children.len() * allocator::ACCOUNTS_PER_CHILD).min(ctx.remaining_accounts.len());
+        let (child_infos, holding_infos) = ctx.remaining_accounts.split_at(child_count);
+        let children = allocator::child_accounts(vault, child_infos)?;
+        allocator::update_children_value(vault, &children)?;
+        let holdings_value =
+            nav::update_holdings_value(vault, holding_infos, clock.unix_timestamp)?;
+        let total_assets = vault.total_assets(ctx.accounts.vault_token_account.amount)?;
+
+        emit!(NavUpdated {
+            vault: vault.key(),
+            holdings_value,
+            total_assets,
+            share_price: fees::share_price(total_assets, vault.share_supply()?)?,
+            timestamp: clock.unix_timestamp,
+        });
+        msg!("Holdings valued at {} (total assets {})", holdings_value, total_assets);
+        Ok(())
+    }
+
+    /// Add a keeper to the vault's execution allowlist
+    pub fn add_keeper(ctx: Context<UpdateVault>, keeper: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        require!(vault.keepers.len() < MAX_KEEPERS, VaultError::TooManyKeepers);
+        require!(
+            !vault.keepers.contains(&keeper),
+            VaultError::KeeperAlreadyRegistered
+        );
+        vault.keepers.push(keeper);
+
+        emit!(KeeperUpdated {
+            vault: vault.key(),
+            keeper,
+            removed: false,
+        });
+        msg!("Keeper {} added", keeper);
+        Ok(())
+    }
+
+    /// Remove a keeper from the vault's execution allowlist
+    pub fn remove_keeper(ctx: Context<UpdateVault>, keeper: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        let index = vault
+            .keepers
+            .iter()
+            .position(|k| *k == keeper)
+            .ok_or(VaultError::KeeperNotFound)?;
+        vault.keepers.remove(index);
+
+        emit!(KeeperUpdated {
+            vault: vault.key(),
+            keeper,
+            removed: true,
+        });
+        msg!("Keeper {} removed", keeper);
+        Ok(())
+    }
+
+    /// Configure the per-execution keeper reward and the permissionless grace period
+    pub fn set_keeper_config(
+        ctx: Context<UpdateVault>,
+        keeper_reward: u64,
+        keeper_grace_period: i64,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        require!(keeper_grace_period >= 0, VaultError::InvalidGracePeriod);
+
+        vault.keeper_reward = keeper_reward;
+        vault.keeper_grace_period = keeper_grace_period;
+
+        emit!(KeeperConfigUpdated {
+            vault: vault.key(),
+            keeper_reward,
+            keeper_grace_period,
+        });
+        msg!(
+            "Keeper reward set to {} with grace period {}s",
+            keeper_reward,
+            keeper_grace_period
+        );
+        Ok(())
+    }
+
+    /// Propose new fee rates, applicable once the fee change timelock has passed.
+    /// A vault without shares has no depositors to protect, so its rates change at once.
+    pub fn propose_fee_change(
+        ctx: Context<UpdateVault>,
+        management_fee_bps: u16,
+        performance_fee_bps: u16,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+
+        require!(
+            management_fee_bps <= fees::MAX_MANAGEMENT_FEE_BPS
+                && performance_fee_bps <= fees::MAX_PERFORMANCE_FEE_BPS,
+            VaultError::FeeTooHigh
+        );
+
+        if vault.share_supply()? == 0 {
+            vault.management_fee_bps = management_fee_bps;
+            vault.performance_fee_bps = performance_fee_bps;
+            vault.pending_fee_change = None;
+
+            emit!(FeeChangeApplied {
+                vault: vault.key(),
+                management_fee_bps,
+                performance_fee_bps,
+                timestamp: clock.unix_timestamp,
+            });
+            msg!(
+                "Fee change applied: management {} bps, performance {} bps",
+                management_fee_bps,
+                performance_fee_bps
+            );
+            return Ok(());
+        }
+
+        let effective_at = clock.unix_timestamp + fees::FEE_CHANGE_TIMELOCK;
+        vault.pending_fee_change = Some(PendingFeeChange {
+            management_fee_bps,
+            performance_fee_bps,
+            effective_at,
+        });
+
+        emit!(FeeChangeProposed {
+            vault: vault.key(),
+            management_fee_bps,
+            performance_fee_bps,
+            effective_at,
+        });
+        msg!(
+            "Fee change proposed: management {} bps, performance {} bps (effective at {})",
+            management_fee_bps,
+            performance_fee_bps,
+            effective_at
+        );
+        Ok(())
+    }
+
+    /// Apply a proposed fee change after its timelock, settling fees at the old rates first
+    pub fn apply_fee_change(ctx: Context<ApplyFeeChange>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+
+        let change = vault
+            .pending_fee_change
+            .ok_or(VaultError::NoPendingFeeChange)?;
+        require!(
+            clock.unix_timestamp >= change.effective_at,
+            VaultError::FeeChangeTimelocked
+        );
+
+        let total_assets = vault.total_assets(ctx.accounts.vault_token_account.amount)?;
+        fees::accrue(vault, total_assets, clock.unix_timestamp)?;
+
+        vault.management_fee_bps = change.management_fee_bps;
+        vault.performance_fee_bps = change.performance_fee_bps;
+        vault.pending_fee_change = None;
+
+        emit!(FeeChangeApplied {
+            vault: vault.key(),
+            management_fee_bps: change.management_fee_bps,
+            performance_fee_bps: change.performance_fee_bps,
+            timestamp: clock.unix_timestamp,
+        });
+        msg!(
+            "Fee change applied: management {} bps, performance {} bps",
+            change.management_fee_bps,
+            change.performance_fee_bps
+        );
+        Ok(())
+    }
+
+    /// Set the account that receives collected fee shares
+    pub fn set_fee_recipient(ctx: Context<UpdateVault>, fee_recipient: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.fee_recipient = fee_recipient;
+
+        emit!(FeeRecipientUpdated {
+            vault: vault.key(),
+            fee_recipient,
+        });
+        msg!("Fee recipient set to {}", fee_recipient);
+        Ok(())
+    }
+
+    /// Accrue outstanding fees and mint all accrued fee shares to the fee recipient
+    pub fn collect_fees(ctx: Context<CollectFees>) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        let clock = Clock::get()?;
+        let total_assets = vault.total_assets(ctx.accounts.vault_token_account.amount)?;
+        fees::accrue(vault, total_assets, clock.unix_timestamp)?;
+
+        let fee_shares = vault.accrued_fee_shares;
+        require!(fee_shares > 0, VaultError::NoFeesToCollect);
+
+        let seeds = vault_signer_seeds!(vault);
+        let signer = &[&seeds[..]];
+
+        let cpi_accounts = MintTo {
+            mint: ctx.accounts.share_mint.to_account_info(),
+            to: ctx.accounts.fee_recipient_share_account.to_account_info(),
+            authority: vault.to_account_info(),
+        };
+        let cpi_program = ctx.accounts.token_program.to_account_info();
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+        token::mint_to(cpi_ctx, fee_shares)?;
+
+        vault.accrued_fee_shares = 0;
+        vault.total_shares = vault.total_shares.checked_add(fee_shares).unwrap();
+
+        ctx.accounts.share_mint.reload()?;
+        require!(
+            ctx.accounts.share_mint.supply == vault.total_shares,
+            VaultError::ShareSupplyMismatch
+        );
+
+        emit!(FeesCollected {
+            vault: vault.key(),
+            fee_recipient: vault.fee_recipient,
+            fee_shares,
+            share_price: fees::share_price(total_assets, vault.share_supply()?)?,
+            timestamp: clock.unix_timestamp,
+        });
+        msg!("Collected {} fee shares", fee_shares);
+        Ok(())
+    }
+
+    /// Set the guardian, who may pause the vault or shut it down alongside the authority
+    pub fn set_guardian(ctx: Context<UpdateVault>, guardian: Pubkey) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        vault.guardian = guardian;
+
+        emit!(GuardianUpdated {
+            vault: vault.key(),
+            guardian,
+        });
+        msg!("Guardian set to {}", guardian);
+        Ok(())
+    }
+
+    /// Set the deposit, withdraw and execute pause flags.
+    /// The guardian may only pause; lifting a pause requires the authority.
+    pub fn set_pause_flags(
+        ctx: Context<GuardianAction>,
+        deposits_paused: bool,
+        withdrawals_paused: bool,
+        execution_paused: bool,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let signer = ctx.accounts.signer.key();
+
+        require!(!vault.is_shutdown, VaultError::VaultIsShutdown);
+        if signer != vault.authority {
+            require!(
+                (deposits_paused || !vault.deposits_paused)
+                    && (withdrawals_paused || !vault.withdrawals_paused)
+                    && (execution_paused || !vault.execution_paused),
+                VaultError::GuardianCannotUnpause
+            );
+        }
+
+        vault.deposits_paused = deposits_paused;
+        vault.withdrawals_paused = withdrawals_paused;
+        vault.execution_paused = execution_paused;
+
+        emit!(PauseFlagsUpdated {
+            vault: vault.key(),
+            deposits_paused,
+            withdrawals_paused,
+            execution_paused,
+            updated_by: signer,
+        });
+        msg!(
+            "Pause flags set: deposits {}, withdrawals {}, execution {}",
+            deposits_paused,
+            withdrawals_paused,
+            execution_paused
+        );
+        Ok(())
+    }
+
+    /// Acknowledge a marked loss beyond the drawdown limit by lowering the share price it is
+    /// measured from, so that the next execution can commit marks down to `share_price`
+    pub fn acknowledge_loss(ctx: Context<GuardianAction>, share_price: u64) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+
+        require!(
+            share_price < vault.last_share_price,
+            VaultError::InvalidLossAcknowledgement
+        );
+        let previous_share_price = vault.last_share_price;
+        vault.last_share_price = share_price;
+
+        emit!(LossAcknowledged {
+            vault: vault.key(),
+            previous_share_price,
+            share_price,
+            acknowledged_by: ctx.accounts.signer.key(),
+            timestamp: Clock::get()?.unix_timestamp,
+        });
+        msg!(
+            "Loss acknowledged: share price {} -> {}",
+            previous_share_price,
+            share_price
+        );
+        Ok(())
+    }
+
+    /// Irreversibly shut the vault down: withdraw everything from its adapters, settle the
+    /// withdrawal queue and stop fees. Withdrawals stay open, paid pro-rata from the liquid
+    /// balance, while holdings are sold off through `unwind_holding` and `settle_hedge`.
+    /// Remaining accounts: as for `execute_strategy`.
+    pub fn shutdown_vault<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ShutdownVault<'info>>,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+
+        require!(!vault.is_shutdown, VaultError::VaultIsShutdown);
+
+        let remaining = strategy::split_remaining_accounts(vault, ctx.remaining_accounts);
+        let adapters = strategy::adapter_accounts(vault, remaining.adapters)?;
+        strategy::sync_adapter_balances(
+            vault,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program.to_account_info(),
+            &adapters,
+        )?;
+        let children = allocator::child_accounts(vault, remaining.children)?;
+        allocator::update_children_value(vault, &children)?;
+        nav::update_holdings_value(vault, remaining.holdings, clock.unix_timestamp)?;
+
+        // Settle fees up to the shutdown; none accrue afterwards
+        let total_assets = vault.total_assets(ctx.accounts.vault_token_account.amount)?;
+        fees::accrue(vault, total_assets, clock.unix_timestamp)?;
+
+        let mut recovered_assets = strategy::unwind(
+            vault,
+            &mut ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program.to_account_info(),
+            &adapters,
+        )?;
+
+        // Child vaults pay out what they hold liquid; the rest stays valued as a position
+        if !children.is_empty() {
+            let programs = allocator_programs(
+                &ctx.accounts.token_program,
+                &ctx.accounts.associated_token_program,
+                &ctx.accounts.system_program,
+            )?;
+            let recovered_from_children = strategy::allocator::unwind(
+                vault,
+                &mut ctx.accounts.vault_token_account,
+                &programs,
+                &children,
+            )?;
+            recovered_assets = recovered_assets
+                .checked_add(recovered_from_children)
+                .ok_or(VaultError::MathOverflow)?;
+        }
+
+        // Every queued request can now be paid from the liquid balance
+        if vault.queued_withdrawal_assets > 0 {
+            vault.claimable_withdrawal_assets = vault
+                .claimable_withdrawal_assets
+                .checked_add(vault.queued_withdrawal_assets)
+                .unwrap();
+            vault.queued_withdrawal_assets = 0;
+            vault.withdrawal_epoch += 1;
+        }
+
+        vault.is_shutdown = true;
+        vault.deposits_paused = true;
+        vault.withdrawals_paused = false;
+        vault.execution_paused = true;
+
+        emit!(VaultShutdown {
+            vault: vault.key(),
+            triggered_by: ctx.accounts.signer.key(),
+            recovered_assets,
+            timestamp: clock.unix_timestamp,
+        });
+        msg!("Vault shut down, recovered {} tokens from adapters", recovered_assets);
+        Ok(())
+    }
+
+    /// Execute vault strategy with guaranteed timing.
+    /// Allowlisted keepers and the authority may execute as soon as a rebalance is due;
+    /// anyone else may execute once the keeper grace period has also elapsed.
+    /// Remaining accounts: for each registered adapter, in registry order, the adapter
+    /// program, the adapter position and the adapter token account; then for each
+    /// registered child vault, in registry order, the accounts listed on
+    /// `allocator::ChildAccounts`; then for each registered holding, in registry order,
+    /// its token account and price account; then, for DeltaNeutral vaults, the accounts
+    /// listed on `strategy::delta_neutral::hedge` for each hedged holding, or for Arbitrage
+    /// vaults executing in a reserved slot, the accounts listed on
+    /// `strategy::arbitrage::LegAccounts` for each market of the route.
+    pub fn execute_strategy<'info>(
+        ctx: Context<'_, '_, 'info, 'info, ExecuteStrategy<'info>>,
+    ) -> Result<()> {
+        let vault = &mut ctx.accounts.vault;
+        let clock = Clock::get()?;
+
+        require!(!vault.is_shutdown, VaultError::VaultIsShutdown);
+        require!(!vault.execution_paused, VaultError::ExecutionPaused);
+
+        // Find the reservation whose window this execution falls in, if one was supplied
+        let reservation_index = match &ctx.accounts.reservation {
+            Some(reservation) => {
+                let index = vault.reservation_index(reservation.key())?;
+                let in_window =
+                    (clock.unix_timestamp - reservation.slot_time).abs() <= vault.slot_tolerance;
+                let usable = match vault.execution_mode {
+                    ExecutionMode::Interval => reservation.status.is_active(),
+                    ExecutionMode::ReservedSlot => {
+                        reservation.status == ReservationStatus::Confirmed
+                    }
+                };
+                (in_window && usable).then_some(index)
+            }
+            None => None,
+        };
+
+        let remaining = strategy::split_remaining_accounts(vault, ctx.remaining_accounts);
+        let adapters = strategy::adapter_accounts(vault, remaining.adapters)?;
+        strategy::sync_adapter_balances(
+            vault,
+            &ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program.to_account_info(),
+            &adapters,
+        )?;
+        let children = allocator::child_accounts(vault, remaining.children)?;
+        allocator::update_children_value(vault, &children)?;
+        nav::update_holdings_value(vault, remaining.holdings, clock.unix_timestamp)?;
+        let nav_before = vault.total_assets(ctx.accounts.vault_token_account.amount)?;
+
+        // Losses marked since the last execution must stay within the drawdown limit
+        // unless the authority or guardian has acknowledged them
+        risk::check_marked_loss(vault, fees::share_price(nav_before, vault.share_supply()?)?)?;
+
+        // Check if rebalancing is due, and since when
+        let due_at = match vault.execution_mode {
+            ExecutionMode::Interval => {
+                let due_at = schedule::next_due(vault);
+                if clock.unix_timestamp >= due_at {
+                    due_at
+                } else {
+                    require!(
+                        schedule::deviation_triggered(vault, nav_before)?,
+                        VaultError::RebalanceTooSoon
+                    );
+                    msg!("Allocation deviation triggered an early rebalance");
+                    clock.unix_timestamp
+                }
+            }
+            ExecutionMode::ReservedSlot => {
+                let index = reservation_index.ok_or(VaultError::ExecutionOutsideReservedWindow)?;
+                vault.reserved_slots[index].slot_time - vault.slot_tolerance
+            }
+        };
+
+        let keeper = ctx.accounts.keeper.key();
+        if keeper != vault.authority && !vault.keepers.contains(&keeper) {
+            require!(
+                clock.unix_timestamp >= due_at + vault.keeper_grace_period,
+                VaultError::UnauthorizedKeeper
+            );
+        }
+
+        // Execute strategy based on type
+        match vault.strategy_type {
+            StrategyType::YieldOptimization => msg!("Executing yield optimization strategy"),
+            StrategyType::DeltaNeutral => msg!("Executing delta-neutral strategy"),
+            StrategyType::Arbitrage => msg!("Executing arbitrage strategy"),
+            StrategyType::Allocator => msg!("Executing allocator strategy"),
+        }
+
+        strategy::rebalance(
+            vault,
+            &mut ctx.accounts.vault_token_account,
+            &ctx.accounts.token_program.to_account_info(),
+            &adapters,
+        )?;
+        if vault.strategy_type == StrategyType::Allocator && !children.is_empty() {
+            let programs = allocator_programs(
+                &ctx.accounts.token_program,
+                &ctx.accounts.associated_token_program,
+                &ctx.accounts.system_program,
+            )?;
+            strategy::allocator::rebalance(
+                vault,
+                &mut ctx.accounts.vault_token_account,
+                &programs,
+                &children,
+            )?;
+        }
+        if vault.strategy_type == StrategyType::DeltaNeutral
+            && vault.holdings.iter().any(|h| h.hedge_market.is_some())
+        {
+            let programs = strategy::delta_neutral::HedgePrograms {
+                dex_program: ctx
+                    .accounts
+                    .dex_program
+                    .as_ref()
+                    .ok_or(VaultError::MissingDexProgram)?
+                    .to_account_info(),
+                payer: ctx.accounts.keeper.to_account_info(),
+                system_program: ctx
+                    .accounts
+                    .system_program
+                    .as_ref()
+                    .ok_or(VaultError::MissingDexProgram)?
+                    .to_account_info(),
+            };
+            strategy::delta_neutral::hedge(
+                vault,
+                remaining.holdings,
+                remaining.dex,
+                &programs,
+                clock.unix_timestamp,
+            )?;
+        }
+        // Both arbitrage legs are placed for the slot reserved for this execution
+        if vault.strategy_type == StrategyType::Arbitrage && vault.arbitrage.is_some() {
+            match (reservation_index, &ctx.accounts.reservation) {
+                (Some(_), Some(reservation)) => {
+                    let programs = strategy::arbitrage::ArbitragePrograms {
+                        dex_program: ctx
+                            .accounts
+                            .dex_program
+                            .as_ref()
+                            .ok_or(VaultError::MissingDexProgram)?
+                            .to_account_info(),
+                        payer: ctx.accounts.keeper.to_account_info(),
+                        system_program: ctx
+                            .accounts
+                            .system_program
+                            .as_ref()
+                            .ok_or(VaultError::MissingDexProgram)?
+                            .to_account_info(),
+                    };
+                    strategy::arbitrage::run(
+                        vault,
+                        &ctx.accounts.vault_token_account,
+                        remaining.holdings,
+                        remaining.dex,
+                        &programs,
+                        reservation.slot_time,
+                        clock.unix_timestamp,
+                    )?;
+                }
+                _ => msg!("Arbitrage only runs inside a reserved slot"),
+            }
+        }
+
+        // Process the withdrawal queue once the rebalance has freed enough liquidity
+        if ctx.accounts.vault_token_account.amount >= vault.reserved_assets()? {
+            vault.claimable_withdrawal_assets = vault
+                .claimable_withdrawal_assets
+                .checked_add(vault.queued_withdrawal_assets)
+                .unwrap();
+            vault.queued_withdrawal_assets = 0;
+            vault.withdrawal_epoch += 1;
+            msg!("Processed withdrawal epoch {}", vault.withdrawal_epoch - 1);
+        }
+
+        let nav_after = vault.total_assets(ctx.accounts.vault_token_account.amount)?;
+        risk::check_rebalance(
+            vault,
+            nav_before,
+            nav_after,
+            strategy::delta_neutral::gross_exposure(vault),
+        )?;
+
+        let fee_shares = fees::accrue(vault, nav_after, clock.unix_timestamp)?;
+
+        // Pay the keeper out of the fee shares accrued by this execution
+        let reward_shares = convert_to_shares(vault.keeper_reward, nav_after, vault.share_supply()?)?
+            .min(fee_shares);
+        if reward_shares > 0 {
+            let seeds = vault_signer_seeds!(vault);
+            let signer = &[&seeds[..]];
+
+            let cpi_accounts = MintTo {
+                mint: ctx.accounts.share_mint.to_account_info(),
+                to: ctx.accounts.keeper_share_account.to_account_info(),
+                authority: vault.to_account_info(),
+            };
+            let cpi_program = ctx.accounts.token_program.to_account_info();
+            let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, signer);
+            token::mint_to(cpi_ctx, reward_shares)?;
+
+            vault.accrued_fee_shares = vault.accrued_fee_shares.checked_sub(reward_shares).unwrap();
+            vault.total_shares = vault.total_shares.checked_add(reward_shares).unwrap();
+
+            ctx.accounts.share_mint.reload()?;
+            require!(
+                ctx.accounts.share_mint.supply == vault.total_shares,
+                VaultError::ShareSupplyMismatch
+            );
+
+            msg!("Keeper {} rewarded {} shares", keeper, reward_shares);
+        }
+
+        // Consume the reservation whose window this execution falls in, if any
+        let mut consumed_reservation = None;
+        if let (Some(index), Some(reservation)) = (reservation_index, &ctx.accounts.reservation) {
+            let orchestrator_program = ctx
+                .accounts
+                .orchestrator_program
+                .as_ref()
+                .ok_or(VaultError::MissingOrchestratorProgram)?;
+
+            let seeds = vault_signer_seeds!(vault);
+            reservation::mark_executed(
+                orchestrator_program.to_account_info(),
+                reservation.to_account_info(),
+                vault.to_account_info(),
+                &[&seeds[..]],
+            )?;
+
+            let consumed = vault.reserved_slots.remove(index);
+            consumed_reservation = Some(consumed.reservation);
+            msg!("Consumed reservation at {}", consumed.slot_time);
+        }
+
+        vault.last_nav_before = nav_before;
+        vault.last_nav_after = nav_after;
+        vault.last_share_price = fees::share_price(nav_after, vault.share_supply()?)?;
+        vault.last_rebalance = clock.unix_timestamp;
+        history::record(
+            &mut *ctx.accounts.history.load_mut()?,
+            vault,
+            nav_after,
+            clock.unix_timestamp,
+        )?;
+
+        // Reserve the slot for the next scheduled rebalance, unless one is already held
+        if let Some(auto_reserve) = vault.schedule.auto_reserve {
+            let slot_time = schedule::next_due(vault);
+            if vault.reserved_slots.len() >= MAX_RESERVATIONS {
+                msg!("Reservations full, next slot at {} not reserved", slot_time);
+            } else if vault.reserved_slots.iter().any(|r| r.slot_time == slot_time) {
+                msg!("Next slot at {} already reserved", slot_time);
+            } else {
+                let (
+                    Some(orchestrator_program),
+                    Some(orchestrator),
+                    Some(next_reservation),
+                    Some(system_program),
+                ) = (
+                    &ctx.accounts.orchestrator_program,
+                    &ctx.accounts.orchestrator,
+                    &ctx.accounts.next_reservation,
+                    &ctx.accounts.system_program,
+                )
+                else {
+                    return err!(VaultError::MissingAutoReserveAccounts);
+                };
+
+                let seeds = vault_signer_seeds!(vault);
+                reservation::reserve(
+                    orchestrator_program.to_account_info(),
+                    orchestrator.to_account_info(),
+                    next_reservation.to_account_info(),
+                    vault.to_account_info(),
+                    ctx.accounts.keeper.to_account_info(),
+                    system_program.to_account_info(),
+                    &[&seeds[..]],
+                    slot_time,
+                    auto_reserve.reservation_type,
+                    auto_reserve.priority,
+                )?;
+
+                vault.reserved_slots.push(VaultReservation {
+                    reservation: next_reservation.key(),
+                    slot_time,
+                });
+
+                emit!(SlotReserved {
+                    vault: vault.key(),
+                    reservation: next_reservation.key(),
+                    slot_time,
+                    reservation_type: auto_reserve.reservation_type,
+                    priority: auto_reserve.priority,
+                    timestamp: clock.unix_timestamp,
+                });
+                msg!("Auto-reserved next execution slot at {}", slot_time);
+            }
+        }
+
+        emit!(StrategyExecuted {
+            vault: vault.key(),
+            keeper,
+            strategy_type: vault.strategy_type,
+            nav_before,
+            nav_after,
+            share_price: fees::share_price(nav_after, vault.share_supply()?)?,
+            fee_shares,
+            keeper_reward_shares: reward_shares,
+            reservation: consumed_reservation,
+            withdrawal_epoch: vault.withdrawal_epoch,
+            timestamp: clock.unix_timestamp,
+        });
+        msg!(
+            "Strategy executed successfully (NAV {} -> {})",
+            nav_before,
+            nav_after
+        );
+        Ok(())
+    }
+}
+
+// Account Structures
+
+#[derive(Accounts)]
+pub struct InitializeRegistry<'info> {
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + VaultRegistry::INIT_SPACE,
+        seeds = [b"vault_registry"],
+        bump
+    )]
+    pub registry: Account<'info, VaultRegistry>,
+
+    #[account(mut)]
+    pub payer: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(vault_id: u64)]
+pub struct InitializeVault<'info> {
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + Vault::INIT_SPACE,
+        seeds = [b"vault", authority.key().as_ref(), &vault_id.to_le_bytes()],
+        bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"vault_registry"],
+        bump = registry.bump
+    )]
+    pub registry: Account<'info, VaultRegistry>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + VaultRegistryEntry::INIT_SPACE,
+        seeds = [b"registry_entry".as_ref(), &registry.vault_count.to_le_bytes()],
+        bump
+    )]
+    pub registry_entry: Account<'info, VaultRegistryEntry>,
+
+    pub asset_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"vault_tokens", vault.key().as_ref()],
+        bump,
+        token::mint = asset_mint,
+        token::authority = vault
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(
+        init,
+        payer = authority,
+        seeds = [b"share_mint", vault.key().as_ref()],
+        bump,
+        mint::decimals = asset_mint.decimals,
+        mint::authority = vault
+    )]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + std::mem::size_of::<ShareHistory>(),
+        seeds = [b"history", vault.key().as_ref()],
+        bump
+    )]
+    pub history: AccountLoader<'info, ShareHistory>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Deposit<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+    
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.mint == vault.asset_mint @ VaultError::InvalidAssetMint,
+        constraint = user_token_account.owner == user.key() @ VaultError::InvalidTokenOwner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, address = vault.share_mint @ VaultError::InvalidShareMint)]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"history", vault.key().as_ref()],
+        bump = history.load()?.bump
+    )]
+    pub history: AccountLoader<'info, ShareHistory>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        associated_token::mint = share_mint,
+        associated_token::authority = user
+    )]
+    pub user_share_account: Account<'info, TokenAccount>,
+
+    /// Required while the vault's allowlist gate is enabled
+    #[account(
+        seeds = [b"allowlist", vault.key().as_ref(), user.key().as_ref()],
+        bump = allowlist_entry.bump
+    )]
+    pub allowlist_entry: Option<Account<'info, AllowlistEntry>>,
+    
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct Withdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+    
+    #[account(
+        mut,
+        constraint = user_token_account.mint == vault.asset_mint @ VaultError::InvalidAssetMint,
+        constraint = user_token_account.owner == user.key() @ VaultError::InvalidTokenOwner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    
+    #[account(mut, address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, address = vault.share_mint @ VaultError::InvalidShareMint)]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"history", vault.key().as_ref()],
+        bump = history.load()?.bump
+    )]
+    pub history: AccountLoader<'info, ShareHistory>,
+
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user
+    )]
+    pub user_share_account: Account<'info, TokenAccount>,
+    
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RequestWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = user,
+        space = 8 + WithdrawalRequest::INIT_SPACE,
+        seeds = [b"withdrawal", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    #[account(
+        init_if_needed,
+        payer = user,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", vault.key().as_ref(), user.key().as_ref()],
+        bump
+    )]
+    pub user_position: Account<'info, UserPosition>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, address = vault.share_mint @ VaultError::InvalidShareMint)]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"history", vault.key().as_ref()],
+        bump = history.load()?.bump
+    )]
+    pub history: AccountLoader<'info, ShareHistory>,
+
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = user
+    )]
+    pub user_share_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ClaimWithdraw<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"withdrawal", vault.key().as_ref(), user.key().as_ref()],
+        bump = withdrawal_request.bump,
+        close = user
+    )]
+    pub withdrawal_request: Account<'info, WithdrawalRequest>,
+
+    #[account(mut)]
+    pub user: Signer<'info>,
+
+    #[account(
+        mut,
+        constraint = user_token_account.mint == vault.asset_mint @ VaultError::InvalidAssetMint,
+        constraint = user_token_account.owner == user.key() @ VaultError::InvalidTokenOwner
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct Preview<'info> {
+    #[account(
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ReadShareHistory<'info> {
+    #[account(
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        seeds = [b"history", vault.key().as_ref()],
+        bump = history.load()?.bump
+    )]
+    pub history: AccountLoader<'info, ShareHistory>,
+}
+
+#[derive(Accounts)]
+pub struct ReserveSlot<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = authority
+    )]
+    pub vault: Account<'info, Vault>,
+    
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub orchestrator: Account<'info, Orchestrator>,
+
+    /// CHECK: initialized by the orchestrator at its reservation PDA for this vault
+    #[account(mut)]
+    pub reservation: UncheckedAccount<'info>,
+
+    pub orchestrator_program: Program<'info, ChronosOrchestrator>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct ManageReservation<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = authority
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub reservation: Account<'info, OrchestratorReservation>,
+
+    pub orchestrator_program: Program<'info, ChronosOrchestrator>,
+}
+
+#[derive(Accounts)]
+pub struct SweepReservations<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub orchestrator_program: Program<'info, ChronosOrchestrator>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = authority
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct AddToAllowlist<'info> {
+    #[account(
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = authority
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        init,
+        payer = authority,
+        space = 8 + AllowlistEntry::INIT_SPACE,
+        seeds = [b"allowlist", vault.key().as_ref(), user.as_ref()],
+        bump
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+#[instruction(user: Pubkey)]
+pub struct RemoveFromAllowlist<'info> {
+    #[account(
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = authority
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"allowlist", vault.key().as_ref(), user.as_ref()],
+        bump = allowlist_entry.bump,
+        close = authority
+    )]
+    pub allowlist_entry: Account<'info, AllowlistEntry>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptAuthority<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        constraint = vault.pending_authority == Some(new_authority.key()) @ VaultError::NotPendingAuthority
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub new_authority: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyStrategyChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(
+        mut,
+        seeds = [b"registry_entry".as_ref(), &vault.registry_index.to_le_bytes()],
+        bump = registry_entry.bump
+    )]
+    pub registry_entry: Account<'info, VaultRegistryEntry>,
+}
+
+#[derive(Accounts)]
+pub struct GuardianAction<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        constraint = signer.key() == vault.authority
+            || signer.key() == vault.guardian @ VaultError::UnauthorizedGuardian
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct ShutdownVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        constraint = signer.key() == vault.authority
+            || signer.key() == vault.guardian @ VaultError::UnauthorizedGuardian
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub signer: Signer<'info>,
+
+    #[account(mut, address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+    /// Required to redeem from child vaults
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+#[derive(Accounts)]
+pub struct AddHolding<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = authority
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub authority: Signer<'info>,
+
+    pub holding_mint: Account<'info, Mint>,
+
+    #[account(
+        token::mint = holding_mint,
+        token::authority = vault
+    )]
+    pub holding_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: decoded and validated as a Pyth-style price account by `nav::read_price`
+    pub oracle: UncheckedAccount<'info>,
+}
+
+#[derive(Accounts)]
+pub struct SetHedgeMarket<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = authority
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub authority: Signer<'info>,
+
+    /// Market to hedge on; omit to stop hedging the holding
+    pub market: Option<Account<'info, DexMarket>>,
+}
+
+#[derive(Accounts)]
+pub struct SetArbitrageConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = authority
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub authority: Signer<'info>,
+
+    /// Markets of the route; omit both to clear it
+    pub market_a: Option<Account<'info, DexMarket>>,
+    pub market_b: Option<Account<'info, DexMarket>>,
+}
+
+#[derive(Accounts)]
+pub struct CancelHedgeOrder<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = authority
+    )]
+    pub vault: Account<'info, Vault>,
+
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub order: Account<'info, DexOrder>,
+
+    pub dex_program: Program<'info, ChronosDex>,
+}
+
+#[derive(Accounts)]
+pub struct UnwindHolding<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        constraint = signer.key() == vault.authority
+            || signer.key() == vault.guardian @ VaultError::UnauthorizedGuardian
+    )]
+    pub vault: Account<'info, Vault>,
+
+    /// Pays rent for the sell order
+    #[account(mut)]
+    pub signer: Signer<'info>,
+
+    pub holding_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: decoded and validated as a Pyth-style price account by `nav::read_price`
+    pub oracle: UncheckedAccount<'info>,
+
+    pub market: Account<'info, DexMarket>,
+
+    /// CHECK: initialized by chronos_dex as the vault's order PDA for the market's current batch
+    #[account(mut)]
+    pub order: UncheckedAccount<'info>,
+
+    pub dex_program: Program<'info, ChronosDex>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SettleHedge<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut, address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holding_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: decoded and validated as a Pyth-style price account by `nav::read_price`
+    pub oracle: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub market: Account<'info, DexMarket>,
+
+    #[account(mut)]
+    pub hedge_order: Account<'info, DexOrder>,
+
+    #[account(mut)]
+    pub counterparty_order: Account<'info, DexOrder>,
+
+    pub counterparty: Signer<'info>,
+
+    #[account(mut)]
+    pub counterparty_base_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub counterparty_quote_account: Account<'info, TokenAccount>,
+
+    pub dex_program: Program<'info, ChronosDex>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct SettleArbitrage<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut, address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub holding_token_account: Account<'info, TokenAccount>,
+
+    /// CHECK: decoded and validated as a Pyth-style price account by `nav::read_price`
+    pub oracle: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub market: Account<'info, DexMarket>,
+
+    #[account(
+        mut,
+        constraint = vault_order.trader == vault.key()
+            && vault_order.market == market.key() @ VaultError::InvalidArbitrageAccounts
+    )]
+    pub vault_order: Account<'info, DexOrder>,
+
+    #[account(mut)]
+    pub counterparty_order: Account<'info, DexOrder>,
+
+    pub counterparty: Signer<'info>,
+
+    #[account(mut)]
+    pub counterparty_base_account: Account<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub counterparty_quote_account: Account<'info, TokenAccount>,
+
+    pub dex_program: Program<'info, ChronosDex>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct AddChildVault<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump,
+        has_one = authority
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut)]
+    pub authority: Signer<'info>,
+
+    #[account(
+        seeds = [b"vault", child_vault.creator.as_ref(), &child_vault.vault_id.to_le_bytes()],
+        bump = child_vault.bump
+    )]
+    pub child_vault: Account<'info, Vault>,
+
+    #[account(address = child_vault.share_mint @ VaultError::InvalidShareMint)]
+    pub child_share_mint: Account<'info, Mint>,
+
+    #[account(
+        init_if_needed,
+        payer = authority,
+        associated_token::mint = child_share_mint,
+        associated_token::authority = vault
+    )]
+    pub child_share_account: Account<'info, TokenAccount>,
+
+    /// The allocator's position in the child, opened here because the allocator cannot pay rent
+    #[account(
+        init_if_needed,
+        payer = authority,
+        space = 8 + UserPosition::INIT_SPACE,
+        seeds = [b"position", child_vault.key().as_ref(), vault.key().as_ref()],
+        bump
+    )]
+    pub child_position: Account<'info, UserPosition>,
+
+    pub token_program: Program<'info, Token>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateNav<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct ApplyFeeChange<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+}
+
+#[derive(Accounts)]
+pub struct CollectFees<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, address = vault.share_mint @ VaultError::InvalidShareMint)]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        token::mint = share_mint,
+        constraint = fee_recipient_share_account.owner == vault.fee_recipient @ VaultError::InvalidTokenOwner
+    )]
+    pub fee_recipient_share_account: Account<'info, TokenAccount>,
+
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct ExecuteStrategy<'info> {
+    #[account(
+        mut,
+        seeds = [b"vault", vault.creator.as_ref(), &vault.vault_id.to_le_bytes()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    #[account(mut, address = vault.vault_token_account @ VaultError::InvalidVaultTokenAccount)]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    #[account(mut, address = vault.share_mint @ VaultError::InvalidShareMint)]
+    pub share_mint: Account<'info, Mint>,
+
+    #[account(
+        mut,
+        seeds = [b"history", vault.key().as_ref()],
+        bump = history.load()?.bump
+    )]
+    pub history: AccountLoader<'info, ShareHistory>,
+
+    /// Pays rent for the next reservation when the schedule auto-reserves
+    #[account(mut)]
+    pub keeper: Signer<'info>,
+
+    #[account(
+        mut,
+        token::mint = share_mint,
+        token::authority = keeper
+    )]
+    pub keeper_share_account: Account<'info, TokenAccount>,
+
+    /// Orchestrator reservation covering this execution, if any
+    #[account(mut)]
+    pub reservation: Option<Account<'info, OrchestratorReservation>>,
+
+    #[account(mut)]
+    pub orchestrator: Option<Account<'info, Orchestrator>>,
+
+    /// CHECK: initialized by the orchestrator at its reservation PDA for the next due slot
+    #[account(mut)]
+    pub next_reservation: Option<UncheckedAccount<'info>>,
+
+    pub orchestrator_program: Option<Program<'info, ChronosOrchestrator>>,
+
+    /// Required to place hedge and arbitrage orders; the keeper pays their rent
+    pub dex_program: Option<Program<'info, ChronosDex>>,
+
+    pub token_program: Program<'info, Token>,
+    /// Required to rebalance child vaults; the system program also funds auto-reservations
+    /// and chronos_dex orders
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    pub system_program: Option<Program<'info, System>>,
+}
+
+// Data Structures
 
 #[account]
 #[derive(InitSpace)]
 pub struct Vault {
+    pub creator: Pubkey,
+    pub vault_id: u64,
+    pub registry_index: u64,
     pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+    pub asset_mint: Pubkey,
+    pub vault_token_account: Pubkey,
+    pub share_mint: Pubkey,
     pub strategy_type: StrategyType,
     pub risk_level: u8,
     pub rebalance_frequency: i64,
-    pub total_deposits: u64,
+    pub deployed_assets: u64,
     pub total_shares: u64,
     pub last_rebalance: i64,
-    #[max_len(10)]
-    pub reserved_slots: Vec<SlotReservation>,
+    #[max_len(MAX_RESERVATIONS)]
+    pub reserved_slots: Vec<VaultReservation>,
+    #[max_len(MAX_ADAPTERS)]
+    pub adapters: Vec<AdapterAllocation>,
+    pub asset_decimals: u8,
+    #[max_len(MAX_HOLDINGS)]
+    pub holdings: Vec<Holding>,
+    pub holdings_value: u64,
+    #[max_len(MAX_CHILD_VAULTS)]
+    pub children: Vec<ChildVault>,
+    pub children_value: u64,
+    #[max_len(MAX_HOLDINGS)]
+    pub hedge_orders: Vec<HedgeOrder>,
+    pub arbitrage: Option<ArbitrageConfig>,
+    pub nav_updated_at: i64,
+    #[max_len(MAX_KEEPERS)]
+    pub keepers: Vec<Pubkey>,
+    pub keeper_reward: u64,
+    pub keeper_grace_period: i64,
+    pub execution_mode: ExecutionMode,
+    pub slot_tolerance: i64,
+    pub fee_recipient: Pubkey,
+    pub management_fee_bps: u16,
+    pub performance_fee_bps: u16,
+    pub high_water_mark: u64,
+    pub last_fee_accrual: i64,
+    pub accrued_fee_shares: u64,
+    pub fee_index: u128,
+    pub pending_fee_change: Option<PendingFeeChange>,
+    pub withdrawal_epoch: u64,
+    pub queued_withdrawal_assets: u64,
+    pub claimable_withdrawal_assets: u64,
+    pub guardian: Pubkey,
+    pub deposits_paused: bool,
+    pub withdrawals_paused: bool,
+    pub execution_paused: bool,
+    pub is_shutdown: bool,
+    pub pending_strategy_change: Option<PendingStrategyChange>,
+    pub deposit_cap: u64,
+    pub user_deposit_cap: u64,
+    pub allowlist_enabled: bool,
+    pub last_nav_before: u64,
+    pub last_nav_after: u64,
+    /// Share price committed by the last execution or loss acknowledgement, from which
+    /// marked losses are measured (0 before the first execution)
+    pub last_share_price: u64,
+    pub schedule: RebalanceSchedule,
     pub bump: u8,
 }
 
+impl Vault {
+    /// Assets under management: the liquid token balance, assets deployed to strategies, the
+    /// last oracle valuation of other holdings and the last valuation of child vault positions,
+    /// less assets owed to withdrawal requests
+    pub fn total_assets(&self, liquid_assets: u64) -> Result<u64> {
+        liquid_assets
+            .checked_add(self.deployed_assets)
+            .and_then(|v| v.checked_add(self.holdings_value))
+            .and_then(|v| v.checked_add(self.children_value))
+            .and_then(|v| v.checked_sub(self.reserved_assets().ok()?))
+            .ok_or(error!(VaultError::MathOverflow))
+    }
+
+    /// Assets shares are redeemed against: all assets under management, or once the vault is
+    /// shut down only its liquid balance, so that withdrawals are paid pro-rata while holdings
+    /// are still being sold off
+    pub fn redeemable_assets(&self, liquid_assets: u64) -> Result<u64> {
+        if self.is_shutdown {
+            Ok(self.available_liquidity(liquid_assets))
+        } else {
+            self.total_assets(liquid_assets)
+        }
+    }
+
+    /// Assets owed to queued and processed withdrawal requests
+    pub fn reserved_assets(&self) -> Result<u64> {
+        self.queued_withdrawal_assets
+            .checked_add(self.claimable_withdrawal_assets)
+            .ok_or(error!(VaultError::MathOverflow))
+    }
+
+    /// Liquid tokens not owed to withdrawal requests
+    pub fn available_liquidity(&self, liquid_assets: u64) -> u64 {
+        liquid_assets.saturating_sub(self.reserved_assets().unwrap_or(u64::MAX))
+    }
+
+    /// Position of the orchestrator reservation `reservation` in `reserved_slots`
+    pub fn reservation_index(&self, reservation: Pubkey) -> Result<usize> {
+        self.reserved_slots
+            .iter()
+            .position(|r| r.reservation == reservation)
+            .ok_or(error!(VaultError::ReservationNotFound))
+    }
+
+    /// `MIN_INITIAL_DEPOSIT_TOKENS` in base units of the asset mint
+    pub fn min_initial_deposit(&self) -> Result<u64> {
+        10u64
+            .checked_pow(self.asset_decimals as u32)
+            .and_then(|unit| unit.checked_mul(MIN_INITIAL_DEPOSIT_TOKENS))
+            .ok_or(error!(VaultError::MathOverflow))
+    }
+
+    /// Shares outstanding including accrued fee shares not yet minted
+    pub fn share_supply(&self) -> Result<u64> {
+        self.total_shares
+            .checked_add(self.accrued_fee_shares)
+            .ok_or(error!(VaultError::MathOverflow))
+    }
+}
+
+/// Program-wide count of vaults; entry `i` lives at `[b"registry_entry", i]`
+#[account]
+#[derive(InitSpace)]
+pub struct VaultRegistry {
+    pub vault_count: u64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct VaultRegistryEntry {
+    pub vault: Pubkey,
+    pub creator: Pubkey,
+    pub vault_id: u64,
+    pub asset_mint: Pubkey,
+    pub strategy_type: StrategyType,
+    pub created_at: i64,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct AllowlistEntry {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub bump: u8,
+}
+
+#[account]
+#[derive(InitSpace)]
+pub struct WithdrawalRequest {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub shares: u64,
+    pub assets: u64,
+    pub epoch: u64,
+    pub requested_at: i64,
+    pub bump: u8,
+}
+
+/// A depositor's position, with cost basis tracked at average cost
 #[account]
 #[derive(InitSpace)]
 pub struct UserPosition {
     pub shares: u64,
-    pub deposited_amount: u64,
+    pub cost_basis: u64,
+    pub realized_pnl: i64,
+    pub last_deposit_at: i64,
+    pub fees_paid: u64,
+    pub fee_index_snapshot: u128,
+}
+
+impl UserPosition {
+    /// Attribute fees charged since the last settlement to the position's shares
+    pub fn settle_fees(&mut self, fee_index: u128) -> Result<()> {
+        let owed = fee_index
+            .checked_sub(self.fee_index_snapshot)
+            .and_then(|delta| delta.checked_mul(self.shares as u128))
+            .ok_or(error!(VaultError::MathOverflow))?
+            / fees::FEE_INDEX_PRECISION;
+        self.fees_paid = u64::try_from(owed)
+            .ok()
+            .and_then(|owed| self.fees_paid.checked_add(owed))
+            .ok_or(error!(VaultError::MathOverflow))?;
+        self.fee_index_snapshot = fee_index;
+        Ok(())
+    }
+
+    pub fn record_deposit(&mut self, assets: u64, shares: u64, now: i64) -> Result<()> {
+        self.shares = self
+            .shares
+            .checked_add(shares)
+            .ok_or(error!(VaultError::MathOverflow))?;
+        self.cost_basis = self
+            .cost_basis
+            .checked_add(assets)
+            .ok_or(error!(VaultError::MathOverflow))?;
+        self.last_deposit_at = now;
+        Ok(())
+    }
+
+    /// Release the average cost of `shares` redeemed for `assets` and return the realized PnL.
+    /// Shares received by transfer have no recorded cost and are left out of the PnL.
+    pub fn record_withdrawal(&mut self, shares: u64, assets: u64) -> Result<i64> {
+        let tracked = shares.min(self.shares);
+        if tracked == 0 {
+            return Ok(0);
+        }
+
+        let cost = (self.cost_basis as u128)
+            .checked_mul(tracked as u128)
+            .ok_or(error!(VaultError::MathOverflow))?
+            / self.shares as u128;
+        let proceeds = (assets as u128)
+            .checked_mul(tracked as u128)
+            .ok_or(error!(VaultError::MathOverflow))?
+            / shares as u128;
+        let pnl = i64::try_from(proceeds as i128 - cost as i128)
+            .map_err(|_| error!(VaultError::MathOverflow))?;
+
+        self.shares -= tracked;
+        self.cost_basis -= cost as u64;
+        self.realized_pnl = self
+            .realized_pnl
+            .checked_add(pnl)
+            .ok_or(error!(VaultError::MathOverflow))?;
+        Ok(pnl)
+    }
+}
+
+/// Ring buffer of vault snapshots, written at every deposit, withdrawal and execution
+#[account(zero_copy)]
+pub struct ShareHistory {
+    pub vault: Pubkey,
+    /// Index the next snapshot is written to
+    pub head: u32,
+    /// Number of snapshots written, up to `SHARE_HISTORY_LEN`
+    pub len: u32,
+    pub bump: u8,
+    pub _padding: [u8; 7],
+    pub snapshots: [ShareSnapshot; SHARE_HISTORY_LEN],
+}
+
+/// The vault's NAV, share price (scaled by `fees::PRICE_PRECISION`) and outstanding shares
+#[zero_copy]
+pub struct ShareSnapshot {
+    pub timestamp: i64,
+    pub nav: u64,
+    pub share_price: u64,
+    pub total_shares: u64,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, InitSpace)]
@@ -307,28 +3475,515 @@ pub enum StrategyType {
     YieldOptimization,
     DeltaNeutral,
     Arbitrage,
+    Allocator,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, InitSpace)]
-pub enum ReservationType {
-    AOT, // Ahead of Time
-    JIT, // Just in Time
+pub enum ExecutionMode {
+    Interval,     // Execute whenever the rebalance frequency has elapsed
+    ReservedSlot, // Execute only inside a confirmed reservation window
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, InitSpace)]
-pub enum ReservationStatus {
-    Pending,
-    Confirmed,
-    Executed,
-    Failed,
+pub enum ScheduleKind {
+    Interval,                        // Due `rebalance_frequency` after the last rebalance
+    AlignedInterval,                 // Due at each multiple of `rebalance_frequency` since the Unix epoch
+    DailyAt { seconds_of_day: u32 }, // Due once a day at a fixed UTC time
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq)]
+pub enum ReservationOutcome {
+    Executed,  // Consumed by an execution or marked executed by the authority
+    Failed,    // Marked failed by the authority
+    Missed,    // Window passed without an execution
+    Cancelled, // Cancelled and closed by the authority
+    Pruned,    // Already finished or closed in the orchestrator
+}
+
+/// A vault-held reservation; its status lives in the orchestrator's reservation account
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct VaultReservation {
+    pub reservation: Pubkey,
+    pub slot_time: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct AdapterAllocation {
+    pub program_id: Pubkey,
+    pub position: Pubkey,
+    pub weight: u16,
+    pub deployed: u64,
+}
+
+/// A child vault an allocator invests in; `last_value` is in vault asset units
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct ChildVault {
+    pub vault: Pubkey,
+    pub share_account: Pubkey,
+    pub weight: u16,
+    pub last_value: u64,
+}
+
+/// A non-asset token held by the vault; `last_value` is in vault asset units
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct Holding {
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub oracle: Pubkey,
+    pub decimals: u8,
+    pub last_value: u64,
+    /// chronos_dex market that DeltaNeutral executions hedge this holding on
+    pub hedge_market: Option<Pubkey>,
+}
+
+/// Two chronos_dex markets trading the same holding against the vault asset, between which
+/// Arbitrage vaults buy low and sell high
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct ArbitrageConfig {
+    pub holding_mint: Pubkey,
+    pub markets: [Pubkey; 2],
+    /// Largest base amount bought and sold in one execution
+    pub max_trade_amount: u64,
+    /// Smallest profit, in vault asset units, that a pair of arbitrage legs must lock in
+    pub min_profit: u64,
+}
+
+/// An open chronos_dex order the vault placed to hedge a holding
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct HedgeOrder {
+    pub holding_mint: Pubkey,
+    pub market: Pubkey,
+    pub order: Pubkey,
+    pub side: OrderSide,
+    /// Quote units per base unit, scaled by `dex::PRICE_SCALE`
+    pub price: u64,
+    pub amount: u64,
+    pub batch_id: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct PendingFeeChange {
+    pub management_fee_bps: u16,
+    pub performance_fee_bps: u16,
+    pub effective_at: i64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct PendingStrategyChange {
+    pub strategy_type: StrategyType,
+    pub risk_level: u8,
+    pub effective_at: i64,
+}
+
+/// When Interval-mode rebalances fall due, and how the next execution slot gets reserved
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
+pub struct RebalanceSchedule {
+    pub kind: ScheduleKind,
+    /// Rebalance early once any adapter drifts this far from its target, in bps of NAV (0 disables)
+    pub deviation_trigger_bps: u16,
+    /// Reserve the next due slot through the orchestrator after every execution
+    pub auto_reserve: Option<AutoReserve>,
 }
 
 #[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, InitSpace)]
-pub struct SlotReservation {
+pub struct AutoReserve {
+    pub reservation_type: RaikuReservationType,
+    pub priority: u8,
+}
+
+// Helper Functions
+
+/// Programs needed to call into child vaults, which are optional on the instruction
+fn allocator_programs<'info>(
+    token_program: &Program<'info, Token>,
+    associated_token_program: &Option<Program<'info, AssociatedToken>>,
+    system_program: &Option<Program<'info, System>>,
+) -> Result<strategy::allocator::AllocatorPrograms<'info>> {
+    let (Some(associated_token_program), Some(system_program)) =
+        (associated_token_program, system_program)
+    else {
+        return err!(VaultError::MissingAllocatorAccounts);
+    };
+    Ok(strategy::allocator::AllocatorPrograms {
+        token_program: token_program.to_account_info(),
+        associated_token_program: associated_token_program.to_account_info(),
+        system_program: system_program.to_account_info(),
+    })
+}
+
+/// Rebalance frequencies must be positive and at most `MAX_REBALANCE_FREQUENCY`
+pub fn check_rebalance_frequency(rebalance_frequency: i64) -> Result<()> {
+    require!(
+        rebalance_frequency > 0 && rebalance_frequency <= MAX_REBALANCE_FREQUENCY,
+        VaultError::InvalidRebalanceFrequency
+    );
+    Ok(())
+}
+
+/// Convert assets to shares against the virtual offsets, rounding down
+pub fn convert_to_shares(assets: u64, total_assets: u64, total_shares: u64) -> Result<u64> {
+    let shares = (assets as u128)
+        .checked_mul(total_shares as u128 + VIRTUAL_SHARES as u128)
+        .and_then(|v| v.checked_div(total_assets as u128 + VIRTUAL_ASSETS as u128))
+        .ok_or(error!(VaultError::MathOverflow))?;
+    u64::try_from(shares).map_err(|_| error!(VaultError::MathOverflow))
+}
+
+/// Convert shares to assets against the virtual offsets, rounding down
+pub fn convert_to_assets(shares: u64, total_assets: u64, total_shares: u64) -> Result<u64> {
+    let assets = (shares as u128)
+        .checked_mul(total_assets as u128 + VIRTUAL_ASSETS as u128)
+        .and_then(|v| v.checked_div(total_shares as u128 + VIRTUAL_SHARES as u128))
+        .ok_or(error!(VaultError::MathOverflow))?;
+    u64::try_from(assets).map_err(|_| error!(VaultError::MathOverflow))
+}
+
+// Events
+
+#[event]
+pub struct VaultInitialized {
+    pub vault: Pubkey,
+    pub creator: Pubkey,
+    pub vault_id: u64,
+    pub registry_index: u64,
+    pub authority: Pubkey,
+    pub asset_mint: Pubkey,
+    pub share_mint: Pubkey,
+    pub strategy_type: StrategyType,
+    pub risk_level: u8,
+    pub rebalance_frequency: i64,
+    pub timestamp: i64,
+}
+
+/// `share_price` is the price the shares were issued or redeemed at, scaled by
+/// `fees::PRICE_PRECISION`
+#[event]
+pub struct Deposited {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub assets: u64,
+    pub shares: u64,
+    pub share_price: u64,
+    pub total_shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct Withdrawn {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub assets: u64,
+    pub shares: u64,
+    pub share_price: u64,
+    pub realized_pnl: i64,
+    pub total_shares: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawalRequested {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub assets: u64,
+    pub shares: u64,
+    pub share_price: u64,
+    pub realized_pnl: i64,
+    pub epoch: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct WithdrawalClaimed {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub assets: u64,
+    pub epoch: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct SlotReserved {
+    pub vault: Pubkey,
+    pub reservation: Pubkey,
     pub slot_time: i64,
-    pub reservation_type: ReservationType,
-    pub status: ReservationStatus,
-    pub reserved_at: i64,
+    pub reservation_type: RaikuReservationType,
+    pub priority: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ReservationReleased {
+    pub vault: Pubkey,
+    pub reservation: Pubkey,
+    pub slot_time: i64,
+    pub outcome: ReservationOutcome,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct NavUpdated {
+    pub vault: Pubkey,
+    pub holdings_value: u64,
+    pub total_assets: u64,
+    pub share_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ExecutionModeUpdated {
+    pub vault: Pubkey,
+    pub execution_mode: ExecutionMode,
+    pub slot_tolerance: i64,
+}
+
+#[event]
+pub struct ScheduleUpdated {
+    pub vault: Pubkey,
+    pub kind: ScheduleKind,
+    pub deviation_trigger_bps: u16,
+    pub auto_reserve: bool,
+    pub next_due: i64,
+}
+
+#[event]
+pub struct AdapterUpdated {
+    pub vault: Pubkey,
+    pub program_id: Pubkey,
+    pub weight: u16,
+    pub removed: bool,
+}
+
+#[event]
+pub struct ChildVaultUpdated {
+    pub vault: Pubkey,
+    pub child: Pubkey,
+    pub weight: u16,
+    pub removed: bool,
+}
+
+#[event]
+pub struct HoldingAdded {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub token_account: Pubkey,
+    pub oracle: Pubkey,
+}
+
+#[event]
+pub struct HoldingRemoved {
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+}
+
+#[event]
+pub struct HedgeMarketUpdated {
+    pub vault: Pubkey,
+    pub holding_mint: Pubkey,
+    pub market: Option<Pubkey>,
+}
+
+#[event]
+pub struct HedgeUpdated {
+    pub vault: Pubkey,
+    pub holding_mint: Pubkey,
+    pub market: Pubkey,
+    pub order: Option<Pubkey>,
+    pub cancelled_order: Option<Pubkey>,
+    pub amount: u64,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct HedgeSettled {
+    pub vault: Pubkey,
+    pub holding_mint: Pubkey,
+    pub market: Pubkey,
+    pub order: Pubkey,
+    pub amount: u64,
+    pub price: u64,
+    /// Unfilled amount of the hedge order after this fill
+    pub remaining: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ArbitrageConfigUpdated {
+    pub vault: Pubkey,
+    pub markets: Option<[Pubkey; 2]>,
+    pub max_trade_amount: u64,
+    pub min_profit: u64,
+}
+
+#[event]
+pub struct ArbitrageOrdersPlaced {
+    pub vault: Pubkey,
+    pub buy_market: Pubkey,
+    pub sell_market: Pubkey,
+    pub buy_order: Pubkey,
+    pub sell_order: Pubkey,
+    pub amount: u64,
+    pub buy_price: u64,
+    pub sell_price: u64,
+    /// Profit locked in once both legs fill
+    pub profit: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct ArbitrageSettled {
+    pub vault: Pubkey,
+    pub market: Pubkey,
+    pub order: Pubkey,
+    pub vault_buys: bool,
+    pub amount: u64,
+    pub price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct KeeperUpdated {
+    pub vault: Pubkey,
+    pub keeper: Pubkey,
+    pub removed: bool,
+}
+
+#[event]
+pub struct KeeperConfigUpdated {
+    pub vault: Pubkey,
+    pub keeper_reward: u64,
+    pub keeper_grace_period: i64,
+}
+
+#[event]
+pub struct FeeChangeProposed {
+    pub vault: Pubkey,
+    pub management_fee_bps: u16,
+    pub performance_fee_bps: u16,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct FeeChangeApplied {
+    pub vault: Pubkey,
+    pub management_fee_bps: u16,
+    pub performance_fee_bps: u16,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct FeeRecipientUpdated {
+    pub vault: Pubkey,
+    pub fee_recipient: Pubkey,
+}
+
+#[event]
+pub struct FeesCollected {
+    pub vault: Pubkey,
+    pub fee_recipient: Pubkey,
+    pub fee_shares: u64,
+    pub share_price: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyExecuted {
+    pub vault: Pubkey,
+    pub keeper: Pubkey,
+    pub strategy_type: StrategyType,
+    pub nav_before: u64,
+    pub nav_after: u64,
+    pub share_price: u64,
+    pub fee_shares: u64,
+    pub keeper_reward_shares: u64,
+    pub reservation: Option<Pubkey>,
+    pub withdrawal_epoch: u64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct AuthorityTransferProposed {
+    pub vault: Pubkey,
+    pub authority: Pubkey,
+    pub pending_authority: Option<Pubkey>,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub vault: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultConfigUpdated {
+    pub vault: Pubkey,
+    pub rebalance_frequency: i64,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct StrategyChangeProposed {
+    pub vault: Pubkey,
+    pub strategy_type: StrategyType,
+    pub risk_level: u8,
+    pub effective_at: i64,
+}
+
+#[event]
+pub struct StrategyChangeApplied {
+    pub vault: Pubkey,
+    pub strategy_type: StrategyType,
+    pub risk_level: u8,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct DepositLimitsUpdated {
+    pub vault: Pubkey,
+    pub deposit_cap: u64,
+    pub user_deposit_cap: u64,
+    pub allowlist_enabled: bool,
+}
+
+#[event]
+pub struct AllowlistUpdated {
+    pub vault: Pubkey,
+    pub user: Pubkey,
+    pub allowed: bool,
+}
+
+#[event]
+pub struct GuardianUpdated {
+    pub vault: Pubkey,
+    pub guardian: Pubkey,
+}
+
+#[event]
+pub struct PauseFlagsUpdated {
+    pub vault: Pubkey,
+    pub deposits_paused: bool,
+    pub withdrawals_paused: bool,
+    pub execution_paused: bool,
+    pub updated_by: Pubkey,
+}
+
+#[event]
+pub struct LossAcknowledged {
+    pub vault: Pubkey,
+    pub previous_share_price: u64,
+    pub share_price: u64,
+    pub acknowledged_by: Pubkey,
+    pub timestamp: i64,
+}
+
+#[event]
+pub struct VaultShutdown {
+    pub vault: Pubkey,
+    pub triggered_by: Pubkey,
+    pub recovered_assets: u64,
+    pub timestamp: i64,
 }
 
 // Errors
@@ -341,5 +3996,189 @@ pub enum VaultError {
     InvalidSlotTime,
     #[msg("Rebalancing attempted too soon")]
     RebalanceTooSoon,
+    #[msg("Token account mint does not match the vault asset mint")]
+    InvalidAssetMint,
+    #[msg("Token account is not owned by the signer")]
+    InvalidTokenOwner,
+    #[msg("Token account is not the vault token account")]
+    InvalidVaultTokenAccount,
+    #[msg("Share mint does not belong to this vault")]
+    InvalidShareMint,
+    #[msg("Share mint supply does not match vault total shares")]
+    ShareSupplyMismatch,
+    #[msg("Initial deposit is below the minimum")]
+    DepositTooSmall,
+    #[msg("Deposit would mint zero shares")]
+    ZeroShares,
+    #[msg("Withdrawal would return zero assets")]
+    ZeroAssets,
+    #[msg("Output is below the caller's minimum")]
+    SlippageExceeded,
+    #[msg("Vault already has the maximum number of adapters")]
+    TooManyAdapters,
+    #[msg("Adapter is already registered")]
+    AdapterAlreadyRegistered,
+    #[msg("Adapter is not registered on this vault")]
+    AdapterNotFound,
+    #[msg("Adapter still holds deployed assets")]
+    AdapterHasDeployedAssets,
+    #[msg("Adapter accounts do not match the vault's adapter registry")]
+    InvalidAdapterAccounts,
+    #[msg("Vault already has the maximum number of keepers")]
+    TooManyKeepers,
+    #[msg("Keeper is already registered")]
+    KeeperAlreadyRegistered,
+    #[msg("Keeper is not registered on this vault")]
+    KeeperNotFound,
+    #[msg("Keeper grace period must not be negative")]
+    InvalidGracePeriod,
+    #[msg("Signer is not an allowlisted keeper and the grace period has not elapsed")]
+    UnauthorizedKeeper,
+    #[msg("Fee exceeds the maximum allowed")]
+    FeeTooHigh,
+    #[msg("No fee change is pending")]
+    NoPendingFeeChange,
+    #[msg("Fee change timelock has not elapsed")]
+    FeeChangeTimelocked,
+    #[msg("No fees to collect")]
+    NoFeesToCollect,
+    #[msg("Risk level must be between 1 and 10")]
+    InvalidRiskLevel,
+    #[msg("Strategy type is not allowed at this risk level")]
+    StrategyNotAllowed,
+    #[msg("Adapter allocation exceeds the risk profile limit")]
+    AdapterAllocationExceeded,
+    #[msg("Leverage exceeds the risk profile limit")]
+    LeverageExceeded,
+    #[msg("Rebalance drawdown exceeds the risk profile limit")]
+    DrawdownExceeded,
+    #[msg("Loss marked since the last execution exceeds the drawdown limit and must be acknowledged")]
+    LossNotAcknowledged,
+    #[msg("Acknowledged share price must be below the committed share price")]
+    InvalidLossAcknowledgement,
+    #[msg("Vault slot reservations are full")]
+    ReservationsFull,
+    #[msg("A reservation already exists for this slot time")]
+    DuplicateReservation,
+    #[msg("Reservation is not tracked by this vault")]
+    ReservationNotFound,
+    #[msg("Orchestrator program account is required to consume a reservation")]
+    MissingOrchestratorProgram,
+    #[msg("Slot tolerance must be between 1 and 300 seconds")]
+    InvalidSlotTolerance,
+    #[msg("Calendar time must fall within a day and deviation trigger within 10000 bps")]
+    InvalidSchedule,
+    #[msg("Auto-reserve requires the orchestrator, next reservation and system program accounts")]
+    MissingAutoReserveAccounts,
+    #[msg("Execution is outside a confirmed reservation window")]
+    ExecutionOutsideReservedWindow,
+    #[msg("Withdrawal exceeds the vault's liquid buffer")]
+    InsufficientLiquidity,
+    #[msg("Withdrawal request has not been processed yet")]
+    WithdrawalNotReady,
+    #[msg("Signer is neither the vault authority nor its guardian")]
+    UnauthorizedGuardian,
+    #[msg("Only the vault authority can lift a pause")]
+    GuardianCannotUnpause,
+    #[msg("Deposits are paused")]
+    DepositsPaused,
+    #[msg("Withdrawals are paused")]
+    WithdrawalsPaused,
+    #[msg("Strategy execution is paused")]
+    ExecutionPaused,
+    #[msg("Vault has been shut down")]
+    VaultIsShutdown,
+    #[msg("Vault has not been shut down")]
+    VaultNotShutdown,
+    #[msg("Signer is not the pending vault authority")]
+    NotPendingAuthority,
+    #[msg("Rebalance frequency must be positive and at most 30 days")]
+    InvalidRebalanceFrequency,
+    #[msg("No strategy change is pending")]
+    NoPendingStrategyChange,
+    #[msg("Strategy change timelock has not elapsed")]
+    StrategyChangeTimelocked,
+    #[msg("Deposit would exceed the vault's TVL cap")]
+    DepositCapExceeded,
+    #[msg("Deposit would exceed the per-user cap")]
+    UserDepositCapExceeded,
+    #[msg("Depositor is not on the vault's allowlist")]
+    NotAllowlisted,
+    #[msg("Vault already has the maximum number of holdings")]
+    TooManyHoldings,
+    #[msg("Holding is already registered")]
+    HoldingAlreadyRegistered,
+    #[msg("Holding is not registered on this vault")]
+    HoldingNotFound,
+    #[msg("Holding was last valued above zero")]
+    HoldingNotEmpty,
+    #[msg("Holding accounts do not match the vault's holdings registry")]
+    InvalidHoldingAccounts,
+    #[msg("Account is not a readable price account")]
+    InvalidOracleAccount,
+    #[msg("Oracle price must be positive")]
+    InvalidOraclePrice,
+    #[msg("Oracle price is stale")]
+    StaleOraclePrice,
+    #[msg("Oracle confidence interval is too wide")]
+    OracleConfidenceTooWide,
+    #[msg("Holdings valuation is stale; call update_nav first")]
+    StaleNav,
+    #[msg("Only allocator vaults invest in child vaults")]
+    NotAllocatorVault,
+    #[msg("Vault already has the maximum number of child vaults")]
+    TooManyChildVaults,
+    #[msg("Child vault is already registered")]
+    ChildVaultAlreadyRegistered,
+    #[msg("Child vault is not registered on this vault")]
+    ChildVaultNotFound,
+    #[msg("Child vault must be a non-allocator vault of the same asset without an allowlist")]
+    InvalidChildVault,
+    #[msg("Child vault position was last valued above zero")]
+    ChildVaultNotEmpty,
+    #[msg("Child vault accounts do not match the vault's child vault registry")]
+    InvalidChildVaultAccounts,
+    #[msg("Child vault calls require the associated token and system program accounts")]
+    MissingAllocatorAccounts,
+    #[msg("Remove all child vaults before leaving the allocator strategy")]
+    ChildVaultsRegistered,
+    #[msg("Adapter did not report its balance as u64 return data")]
+    InvalidAdapterBalance,
+    #[msg("Hedge market must trade the holding against the vault asset")]
+    InvalidHedgeMarket,
+    #[msg("Hedge accounts do not match the vault's hedged holdings")]
+    InvalidHedgeAccounts,
+    #[msg("Holding has a tracked hedge order; cancel it first")]
+    HedgeOrderOpen,
+    #[msg("Hedge order is not tracked on this vault")]
+    HedgeOrderNotFound,
+    #[msg("Hedge order is partially filled and stays live on the DEX")]
+    HedgeOrderPartiallyFilled,
+    #[msg("Holding has no balance to unwind")]
+    NothingToUnwind,
+    #[msg("Trading on chronos_dex requires the DEX and system program accounts")]
+    MissingDexProgram,
+    #[msg("Arbitrage markets must be two distinct markets of a registered holding and the vault asset")]
+    InvalidArbitrageConfig,
+    #[msg("Arbitrage accounts do not match the vault's arbitrage route")]
+    InvalidArbitrageAccounts,
+    #[msg("Arbitrage profit is below the configured floor")]
+    ArbitrageProfitBelowFloor,
+    #[msg("Arbitrage fill is on the wrong side of the oracle price")]
+    ArbitragePriceOffOracle,
+    #[msg("Holding is traded by the vault's arbitrage route")]
+    HoldingUsedForArbitrage,
+    #[msg("History window must be positive")]
+    InvalidHistoryWindow,
+    #[msg("Share history does not cover the requested window")]
+    InsufficientHistory,
+    #[msg("Share history snapshot has a zero share price")]
+    InvalidHistorySnapshot,
+    #[msg("Hedge settlement price is below the holding's oracle price")]
+    HedgePriceBelowOracle,
+    #[msg("Asset mint has more decimals than the vault supports")]
+    UnsupportedAssetDecimals,
+    #[msg("Arithmetic overflow")]
+    MathOverflow,
 }
 
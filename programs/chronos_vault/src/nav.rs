@@ -0,0 +1,153 @@
+use crate::{Vault, VaultError};
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+/// Program that owns the price accounts holdings are valued from
+pub const ORACLE_PROGRAM_ID: Pubkey = chronos_mock_oracle::ID;
+/// Number of remaining accounts each holding takes: token account and price account
+pub const ACCOUNTS_PER_HOLDING: usize = 2;
+/// Oldest oracle price accepted, in seconds
+pub const MAX_PRICE_AGE: i64 = 60;
+/// Widest oracle confidence interval accepted, relative to the price
+pub const MAX_CONFIDENCE_BPS: u64 = 200;
+/// Oldest cached holdings value that deposits and withdrawals may be priced against
+pub const MAX_NAV_AGE: i64 = 60;
+
+/// Pyth-style price fields at the start of a price account, after its 8-byte discriminator.
+/// The value is `price * 10^expo` vault asset units per whole holding unit.
+#[derive(AnchorDeserialize, Clone, Copy, Debug)]
+pub struct OraclePrice {
+    pub price: i64,
+    pub conf: u64,
+    pub expo: i32,
+    pub publish_time: i64,
+}
+
+/// Decode a price account owned by the oracle program, rejecting non-positive, stale or
+/// low-confidence prices
+pub fn read_price(oracle: &AccountInfo, now: i64) -> Result<OraclePrice> {
+    require_keys_eq!(
+        *oracle.owner,
+        ORACLE_PROGRAM_ID,
+        VaultError::InvalidOracleAccount
+    );
+    let data = oracle.try_borrow_data()?;
+    let price = data
+        .get(8..)
+        .and_then(|mut body| OraclePrice::deserialize(&mut body).ok())
+        .ok_or(error!(VaultError::InvalidOracleAccount))?;
+
+    require!(price.price > 0, VaultError::InvalidOraclePrice);
+    require!(
+        now.saturating_sub(price.publish_time) <= MAX_PRICE_AGE,
+        VaultError::StaleOraclePrice
+    );
+    require!(
+        (price.conf as u128) * 10_000 <= (price.price as u128) * MAX_CONFIDENCE_BPS as u128,
+        VaultError::OracleConfidenceTooWide
+    );
+
+    Ok(price)
+}
+
+/// Value of `amount` base units of a holding, in base units of the vault asset
+pub fn holding_value(
+    amount: u64,
+    price: &OraclePrice,
+    holding_decimals: u8,
+    asset_decimals: u8,
+) -> Result<u64> {
+    let exponent = price.expo + asset_decimals as i32 - holding_decimals as i32;
+    let value = (amount as u128)
+        .checked_mul(price.price as u128)
+        .ok_or(error!(VaultError::MathOverflow))?;
+    let value = if exponent >= 0 {
+        10u128
+            .checked_pow(exponent.unsigned_abs())
+            .and_then(|scale| value.checked_mul(scale))
+            .ok_or(error!(VaultError::MathOverflow))?
+    } else {
+        // A divisor past u128 leaves nothing of a u128 value
+        10u128
+            .checked_pow(exponent.unsigned_abs())
+            .map_or(0, |scale| value / scale)
+    };
+    u64::try_from(value).map_err(|_| error!(VaultError::MathOverflow))
+}
+
+/// Re-value every holding from its token account and oracle, caching the total on the vault.
+/// Accounts: for each registered holding, in registry order, its token account and price account.
+pub fn update_holdings_value(vault: &mut Vault, accounts: &[AccountInfo], now: i64) -> Result<u64> {
+    require!(
+        accounts.len() == vault.holdings.len() * ACCOUNTS_PER_HOLDING,
+        VaultError::InvalidHoldingAccounts
+    );
+
+    let asset_decimals = vault.asset_decimals;
+    let mut total: u64 = 0;
+    for (holding, accounts) in vault
+        .holdings
+        .iter_mut()
+        .zip(accounts.chunks(ACCOUNTS_PER_HOLDING))
+    {
+        require_keys_eq!(
+            accounts[0].key(),
+            holding.token_account,
+            VaultError::InvalidHoldingAccounts
+        );
+        require_keys_eq!(
+            accounts[1].key(),
+            holding.oracle,
+            VaultError::InvalidHoldingAccounts
+        );
+
+        // The key check above ties this to the token account validated by `add_holding`
+        let token_account =
+            TokenAccount::try_deserialize(&mut &accounts[0].try_borrow_data()?[..])?;
+        let price = read_price(&accounts[1], now)?;
+        holding.last_value = holding_value(
+            token_account.amount,
+            &price,
+            holding.decimals,
+            asset_decimals,
+        )?;
+        total = total
+            .checked_add(holding.last_value)
+            .ok_or(error!(VaultError::MathOverflow))?;
+    }
+
+    vault.holdings_value = total;
+    vault.nav_updated_at = now;
+    Ok(total)
+}
+
+/// Re-value the holding at `index` at a new token `balance`, keeping `vault.holdings_value`
+/// in step. Returns the holding's new value.
+pub fn revalue_holding(
+    vault: &mut Vault,
+    index: usize,
+    balance: u64,
+    price: &OraclePrice,
+) -> Result<u64> {
+    let holding = vault.holdings[index];
+    let value = holding_value(balance, price, holding.decimals, vault.asset_decimals)?;
+    vault.holdings_value = vault
+        .holdings_value
+        .checked_sub(holding.last_value)
+        .and_then(|v| v.checked_add(value))
+        .ok_or(error!(VaultError::MathOverflow))?;
+    vault.holdings[index].last_value = value;
+    Ok(value)
+}
+
+/// Reject pricing shares against cached holding and child vault values older than `MAX_NAV_AGE`
+pub fn check_fresh(vault: &Vault, now: i64) -> Result<()> {
+    if vault.holdings.is_empty() && vault.children.is_empty() {
+        return Ok(());
+    }
+    require!(
+        now.saturating_sub(vault.nav_updated_at) <= MAX_NAV_AGE,
+        VaultError::StaleNav
+    );
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use anchor_lang::prelude::*;
+use chronos_orchestrator::cpi::accounts::{CancelReservation, ReserveRaikuSlot, UpdateReservation};
+use chronos_orchestrator::RaikuReservationType;
+
+/// Create an orchestrator reservation with the vault as requester; `payer` funds its rent
+#[allow(clippy::too_many_arguments)]
+pub fn reserve<'info>(
+    orchestrator_program: AccountInfo<'info>,
+    orchestrator: AccountInfo<'info>,
+    reservation: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    payer: AccountInfo<'info>,
+    system_program: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+    slot_time: i64,
+    reservation_type: RaikuReservationType,
+    priority: u8,
+) -> Result<()> {
+    let cpi_accounts = ReserveRaikuSlot {
+        orchestrator,
+        reservation,
+        requester: vault,
+        payer,
+        system_program,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(orchestrator_program, cpi_accounts, signer_seeds);
+    chronos_orchestrator::cpi::reserve_raiku_slot(cpi_ctx, slot_time, reservation_type, priority)
+}
+
+/// Mark an orchestrator reservation executed, signing as the requesting vault
+pub fn mark_executed<'info>(
+    orchestrator_program: AccountInfo<'info>,
+    reservation: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_accounts = UpdateReservation {
+        reservation,
+        requester: vault,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(orchestrator_program, cpi_accounts, signer_seeds);
+    chronos_orchestrator::cpi::mark_reservation_executed(cpi_ctx)
+}
+
+/// Mark an orchestrator reservation failed, signing as the requesting vault
+pub fn mark_failed<'info>(
+    orchestrator_program: AccountInfo<'info>,
+    reservation: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_accounts = UpdateReservation {
+        reservation,
+        requester: vault,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(orchestrator_program, cpi_accounts, signer_seeds);
+    chronos_orchestrator::cpi::mark_reservation_failed(cpi_ctx)
+}
+
+/// Cancel an orchestrator reservation, returning its rent to `rent_receiver`
+pub fn cancel<'info>(
+    orchestrator_program: AccountInfo<'info>,
+    reservation: AccountInfo<'info>,
+    vault: AccountInfo<'info>,
+    rent_receiver: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_accounts = CancelReservation {
+        reservation,
+        requester: vault,
+        rent_receiver,
+    };
+    let cpi_ctx = CpiContext::new_with_signer(orchestrator_program, cpi_accounts, signer_seeds);
+    chronos_orchestrator::cpi::cancel_reservation(cpi_ctx)
+}
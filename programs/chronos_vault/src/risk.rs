@@ -0,0 +1,139 @@
+use crate::{StrategyType, Vault, VaultError};
+use anchor_lang::prelude::*;
+
+pub const MIN_RISK_LEVEL: u8 = 1;
+pub const MAX_RISK_LEVEL: u8 = 10;
+
+/// On-chain limits implied by a vault's `risk_level`
+pub struct RiskProfile {
+    /// Maximum share of NAV deployed to any single adapter or child vault
+    pub max_adapter_allocation_bps: u16,
+    /// Maximum gross exposure, long positions plus short hedges, relative to NAV for
+    /// DeltaNeutral vaults (10_000 = 1x)
+    pub max_leverage_bps: u32,
+    /// Maximum NAV loss tolerated across a single rebalance, and maximum share price loss
+    /// marked between executions without an acknowledgement
+    pub max_drawdown_bps: u16,
+    pub allowed_strategies: &'static [StrategyType],
+}
+
+const CONSERVATIVE: RiskProfile = RiskProfile {
+    max_adapter_allocation_bps: 3_000,
+    max_leverage_bps: 10_000,
+    max_drawdown_bps: 50,
+    allowed_strategies: &[StrategyType::YieldOptimization, StrategyType::Allocator],
+};
+
+const MODERATE: RiskProfile = RiskProfile {
+    max_adapter_allocation_bps: 5_000,
+    max_leverage_bps: 15_000,
+    max_drawdown_bps: 200,
+    allowed_strategies: &[
+        StrategyType::YieldOptimization,
+        StrategyType::DeltaNeutral,
+        StrategyType::Allocator,
+    ],
+};
+
+const AGGRESSIVE: RiskProfile = RiskProfile {
+    max_adapter_allocation_bps: 10_000,
+    max_leverage_bps: 30_000,
+    max_drawdown_bps: 500,
+    allowed_strategies: &[
+        StrategyType::YieldOptimization,
+        StrategyType::DeltaNeutral,
+        StrategyType::Arbitrage,
+        StrategyType::Allocator,
+    ],
+};
+
+/// Risk profile for a risk level: 1-3 conservative, 4-6 moderate, 7-10 aggressive
+pub fn profile(risk_level: u8) -> Result<&'static RiskProfile> {
+    match risk_level {
+        1..=3 => Ok(&CONSERVATIVE),
+        4..=6 => Ok(&MODERATE),
+        7..=10 => Ok(&AGGRESSIVE),
+        _ => err!(VaultError::InvalidRiskLevel),
+    }
+}
+
+/// Reject a strategy the risk level does not allow
+pub fn check_strategy(risk_level: u8, strategy_type: StrategyType) -> Result<()> {
+    require!(
+        profile(risk_level)?
+            .allowed_strategies
+            .contains(&strategy_type),
+        VaultError::StrategyNotAllowed
+    );
+    Ok(())
+}
+
+/// Check the loss marked since the last execution, as a fall in share price from
+/// `vault.last_share_price`
+pub fn check_marked_loss(vault: &Vault, share_price: u64) -> Result<()> {
+    if share_price >= vault.last_share_price {
+        return Ok(());
+    }
+    let max_loss = bps_of(
+        vault.last_share_price,
+        profile(vault.risk_level)?.max_drawdown_bps as u64,
+    );
+    require!(
+        ((vault.last_share_price - share_price) as u128) <= max_loss,
+        VaultError::LossNotAcknowledged
+    );
+    Ok(())
+}
+
+/// Check the vault's post-rebalance state against its risk profile. `nav_before` is the NAV
+/// once this execution has re-valued its positions, so the drawdown check covers only the
+/// value the rebalance itself moves.
+pub fn check_rebalance(
+    vault: &Vault,
+    nav_before: u64,
+    nav_after: u64,
+    gross_exposure: u64,
+) -> Result<()> {
+    let profile = profile(vault.risk_level)?;
+    require!(
+        profile.allowed_strategies.contains(&vault.strategy_type),
+        VaultError::StrategyNotAllowed
+    );
+
+    let max_allocation = bps_of(nav_after, profile.max_adapter_allocation_bps as u64);
+    require!(
+        vault
+            .adapters
+            .iter()
+            .all(|a| a.deployed as u128 <= max_allocation),
+        VaultError::AdapterAllocationExceeded
+    );
+    require!(
+        vault
+            .children
+            .iter()
+            .all(|c| c.last_value as u128 <= max_allocation),
+        VaultError::AdapterAllocationExceeded
+    );
+
+    if vault.strategy_type == StrategyType::DeltaNeutral {
+        require!(
+            gross_exposure as u128 <= bps_of(nav_after, profile.max_leverage_bps as u64),
+            VaultError::LeverageExceeded
+        );
+    }
+
+    if nav_after < nav_before {
+        let max_loss = bps_of(nav_before, profile.max_drawdown_bps as u64);
+        require!(
+            ((nav_before - nav_after) as u128) <= max_loss,
+            VaultError::DrawdownExceeded
+        );
+    }
+
+    Ok(())
+}
+
+fn bps_of(amount: u64, bps: u64) -> u128 {
+    amount as u128 * bps as u128 / 10_000
+}
@@ -0,0 +1,59 @@
+use crate::{strategy, ScheduleKind, Vault, VaultError};
+use anchor_lang::prelude::*;
+
+pub const SECONDS_PER_DAY: i64 = 86_400;
+
+/// Reject calendar times outside a day and deviation triggers above 100%
+pub fn check_schedule(kind: ScheduleKind, deviation_trigger_bps: u16) -> Result<()> {
+    if let ScheduleKind::DailyAt { seconds_of_day } = kind {
+        require!(
+            (seconds_of_day as i64) < SECONDS_PER_DAY,
+            VaultError::InvalidSchedule
+        );
+    }
+    require!(deviation_trigger_bps <= 10_000, VaultError::InvalidSchedule);
+    Ok(())
+}
+
+/// First time after the last rebalance at which the next one falls due
+pub fn next_due(vault: &Vault) -> i64 {
+    let last = vault.last_rebalance;
+    let frequency = vault.rebalance_frequency;
+    match vault.schedule.kind {
+        ScheduleKind::Interval => last + frequency,
+        ScheduleKind::AlignedInterval => (last.div_euclid(frequency) + 1) * frequency,
+        ScheduleKind::DailyAt { seconds_of_day } => {
+            let at = last - last.rem_euclid(SECONDS_PER_DAY) + seconds_of_day as i64;
+            if at > last {
+                at
+            } else {
+                at + SECONDS_PER_DAY
+            }
+        }
+    }
+}
+
+/// Largest gap between an adapter's deployed amount and its target, in bps of total assets
+pub fn max_deviation_bps(vault: &Vault, total_assets: u64) -> Result<u64> {
+    if total_assets == 0 {
+        return Ok(0);
+    }
+
+    let targets = strategy::target_allocations(vault, total_assets)?;
+    let max_gap = vault
+        .adapters
+        .iter()
+        .zip(targets)
+        .map(|(adapter, target)| adapter.deployed.abs_diff(target))
+        .max()
+        .unwrap_or(0);
+
+    let bps = (max_gap as u128) * 10_000 / total_assets as u128;
+    u64::try_from(bps).map_err(|_| error!(VaultError::MathOverflow))
+}
+
+/// Whether allocations have drifted far enough to rebalance ahead of schedule
+pub fn deviation_triggered(vault: &Vault, total_assets: u64) -> Result<bool> {
+    let trigger = vault.schedule.deviation_trigger_bps;
+    Ok(trigger > 0 && max_deviation_bps(vault, total_assets)? >= trigger as u64)
+}
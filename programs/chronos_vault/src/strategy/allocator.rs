@@ -0,0 +1,134 @@
+use super::weighted_targets;
+use crate::allocator::{self, AllocatorAccounts, ChildAccounts, ChildState};
+use crate::{ChildVault, Vault, VaultError};
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+/// Share of assets deployed to child vaults; the rest stays liquid for withdrawals
+pub const DEPLOYED_BPS: u16 = 9_000;
+
+/// Spread most of the vault across child vaults by weight
+pub fn target_allocations(children: &[ChildVault], total_assets: u64) -> Result<Vec<u64>> {
+    weighted_targets(children, total_assets, DEPLOYED_BPS)
+}
+
+/// Move funds between the allocator and its child vaults toward their target weights.
+/// Redemptions run first, limited to each child's instant liquidity, so that freed
+/// assets can fund deposits in the same pass.
+pub fn rebalance<'info>(
+    vault: &mut Account<'info, Vault>,
+    vault_token_account: &mut Account<'info, TokenAccount>,
+    programs: &AllocatorPrograms<'info>,
+    children: &[ChildAccounts<'info>],
+) -> Result<()> {
+    let total_assets = vault.total_assets(vault_token_account.amount)?;
+    let targets = target_allocations(&vault.children, total_assets)?;
+
+    let creator = vault.creator;
+    let vault_id = vault.vault_id.to_le_bytes();
+    let bump = [vault.bump];
+    let seeds: &[&[u8]] = &[b"vault", creator.as_ref(), &vault_id, &bump];
+    let signer = &[seeds];
+
+    let accounts = programs.with_vault(vault, vault_token_account);
+
+    for (i, child) in children.iter().enumerate() {
+        let value = vault.children[i].last_value;
+        if value <= targets[i] {
+            continue;
+        }
+        let state = ChildState::load(child)?;
+        if state.vault.withdrawals_paused {
+            continue;
+        }
+        let shares = state.redeemable_shares(value - targets[i])?;
+        if shares > 0 {
+            allocator::withdraw(&accounts, child, signer, shares)?;
+        }
+    }
+
+    vault_token_account.reload()?;
+
+    for (i, child) in children.iter().enumerate() {
+        let value = vault.children[i].last_value;
+        if value >= targets[i] {
+            continue;
+        }
+        let state = ChildState::load(child)?;
+        if state.vault.is_shutdown || state.vault.deposits_paused {
+            continue;
+        }
+        let amount =
+            (targets[i] - value).min(vault.available_liquidity(vault_token_account.amount));
+        let below_minimum =
+            state.vault.share_supply()? == 0 && amount < state.vault.min_initial_deposit()?;
+        if amount == 0 || below_minimum {
+            continue;
+        }
+        allocator::deposit(&accounts, child, signer, amount)?;
+        vault_token_account.reload()?;
+    }
+
+    allocator::update_children_value(vault, children)?;
+    Ok(())
+}
+
+/// Redeem as much of every child position as the children can pay out instantly,
+/// returning the amount recovered
+pub fn unwind<'info>(
+    vault: &mut Account<'info, Vault>,
+    vault_token_account: &mut Account<'info, TokenAccount>,
+    programs: &AllocatorPrograms<'info>,
+    children: &[ChildAccounts<'info>],
+) -> Result<u64> {
+    let creator = vault.creator;
+    let vault_id = vault.vault_id.to_le_bytes();
+    let bump = [vault.bump];
+    let seeds: &[&[u8]] = &[b"vault", creator.as_ref(), &vault_id, &bump];
+    let signer = &[seeds];
+
+    let liquid_before = vault_token_account.amount;
+    let accounts = programs.with_vault(vault, vault_token_account);
+
+    for child in children {
+        let state = ChildState::load(child)?;
+        if state.vault.withdrawals_paused {
+            continue;
+        }
+        let shares = state.redeemable_shares(u64::MAX)?;
+        if shares > 0 {
+            allocator::withdraw(&accounts, child, signer, shares)?;
+        }
+    }
+
+    vault_token_account.reload()?;
+    allocator::update_children_value(vault, children)?;
+
+    vault_token_account
+        .amount
+        .checked_sub(liquid_before)
+        .ok_or(error!(VaultError::MathOverflow))
+}
+
+/// Programs the child vault deposit and withdraw instructions expect
+pub struct AllocatorPrograms<'info> {
+    pub token_program: AccountInfo<'info>,
+    pub associated_token_program: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+impl<'info> AllocatorPrograms<'info> {
+    fn with_vault(
+        &self,
+        vault: &Account<'info, Vault>,
+        vault_token_account: &Account<'info, TokenAccount>,
+    ) -> AllocatorAccounts<'info> {
+        AllocatorAccounts {
+            vault: vault.to_account_info(),
+            vault_token_account: vault_token_account.to_account_info(),
+            token_program: self.token_program.clone(),
+            associated_token_program: self.associated_token_program.clone(),
+            system_program: self.system_program.clone(),
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use crate::nav::{self, ACCOUNTS_PER_HOLDING};
+use crate::{dex, AdapterAllocation, ArbitrageOrdersPlaced, Vault, VaultError};
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use chronos_dex::{Market, OrderSide};
+
+/// Number of remaining accounts the caller supplies per arbitrage market
+pub const ACCOUNTS_PER_LEG: usize = 2;
+/// Oldest market clearing price the arbitrage legs are quoted from
+pub const MAX_CLEARING_PRICE_AGE: i64 = 60;
+
+/// Arbitrage needs the full balance on hand, so everything is recalled from adapters
+pub fn target_allocations(adapters: &[AdapterAllocation], _total_assets: u64) -> Result<Vec<u64>> {
+    Ok(vec![0; adapters.len()])
+}
+
+/// Accounts of one arbitrage market, taken from the instruction's remaining accounts in the
+/// order: market, the vault's order PDA for the market's current batch
+pub struct LegAccounts<'info> {
+    pub market: AccountInfo<'info>,
+    pub vault_order: AccountInfo<'info>,
+}
+
+/// Programs and accounts the arbitrage legs are placed with
+pub struct ArbitragePrograms<'info> {
+    pub dex_program: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+/// Quote the vault's arbitrage holding from each market's last clearing price and, when the
+/// cheaper market clears below the oracle price and the dearer one above it, place a buy
+/// order on the first and a sell order on the second for `slot_time`, limited at those
+/// clearing prices. Counterparties fill the legs through `settle_arbitrage`. A spread that
+/// locks in less than the configured floor reverts; stale or missing clearing prices skip.
+/// Returns the profit the legs lock in once both fill, or `None` when there was nothing to trade.
+/// `holding_accounts` are the holding accounts already checked by `nav::update_holdings_value`;
+/// `leg_accounts` are the accounts listed on `LegAccounts` for each configured market, in order.
+pub fn run<'info>(
+    vault: &mut Account<'info, Vault>,
+    vault_token_account: &Account<'info, TokenAccount>,
+    holding_accounts: &[AccountInfo<'info>],
+    leg_accounts: &[AccountInfo<'info>],
+    programs: &ArbitragePrograms<'info>,
+    slot_time: i64,
+    now: i64,
+) -> Result<Option<u64>> {
+    let Some(config) = vault.arbitrage else {
+        return Ok(None);
+    };
+    if leg_accounts.is_empty() {
+        msg!("No arbitrage markets supplied, skipping arbitrage");
+        return Ok(None);
+    }
+    require!(
+        leg_accounts.len() == config.markets.len() * ACCOUNTS_PER_LEG,
+        VaultError::InvalidArbitrageAccounts
+    );
+
+    let legs: Vec<LegAccounts<'info>> = config
+        .markets
+        .iter()
+        .zip(leg_accounts.chunks(ACCOUNTS_PER_LEG))
+        .map(|(market, accounts)| {
+            require_keys_eq!(
+                accounts[0].key(),
+                *market,
+                VaultError::InvalidArbitrageAccounts
+            );
+            Ok(LegAccounts {
+                market: accounts[0].clone(),
+                vault_order: accounts[1].clone(),
+            })
+        })
+        .collect::<Result<_>>()?;
+    let markets = [load::<Market>(&legs[0].market)?, load::<Market>(&legs[1].market)?];
+    if markets.iter().any(|m| {
+        m.last_clearing_price == 0 || now.saturating_sub(m.last_clearing_at) > MAX_CLEARING_PRICE_AGE
+    }) {
+        msg!("Market clearing prices are missing or stale, skipping arbitrage");
+        return Ok(None);
+    }
+
+    // The vault buys on the market that last cleared lower, and sells on the other
+    let (buy, sell) = if markets[0].last_clearing_price <= markets[1].last_clearing_price {
+        (0, 1)
+    } else {
+        (1, 0)
+    };
+    let (buy_price, sell_price) = (
+        markets[buy].last_clearing_price,
+        markets[sell].last_clearing_price,
+    );
+
+    // Reference price of the holding from its oracle, in DEX price units
+    let index = vault
+        .holdings
+        .iter()
+        .position(|h| h.mint == config.holding_mint)
+        .ok_or(VaultError::InvalidArbitrageConfig)?;
+    let holding = vault.holdings[index];
+    let oracle_price = nav::read_price(&holding_accounts[index * ACCOUNTS_PER_HOLDING + 1], now)?;
+    let reference_price = nav::holding_value(
+        dex::PRICE_SCALE,
+        &oracle_price,
+        holding.decimals,
+        vault.asset_decimals,
+    )?;
+
+    // Only buy below and sell above the reference price
+    if buy_price >= reference_price || sell_price <= reference_price {
+        msg!(
+            "Clearing prices {} and {} do not straddle reference price {}",
+            buy_price,
+            sell_price,
+            reference_price
+        );
+        return Ok(None);
+    }
+
+    let affordable = (vault.available_liquidity(vault_token_account.amount) as u128)
+        * dex::PRICE_SCALE as u128
+        / buy_price as u128;
+    let amount = config
+        .max_trade_amount
+        .min(u64::try_from(affordable).unwrap_or(u64::MAX));
+    if amount == 0 {
+        msg!("No liquidity available to arbitrage");
+        return Ok(None);
+    }
+
+    let profit = u64::try_from(
+        amount as u128 * (sell_price - buy_price) as u128 / dex::PRICE_SCALE as u128,
+    )
+    .map_err(|_| error!(VaultError::MathOverflow))?;
+    require!(
+        profit >= config.min_profit,
+        VaultError::ArbitrageProfitBelowFloor
+    );
+
+    let creator = vault.creator;
+    let vault_id = vault.vault_id.to_le_bytes();
+    let bump = [vault.bump];
+    let seeds: &[&[u8]] = &[b"vault", creator.as_ref(), &vault_id, &bump];
+    let signer = &[seeds];
+
+    let vault_info = vault.to_account_info();
+    let order_time = slot_time.max(now);
+    for (leg, side, price) in [
+        (&legs[buy], OrderSide::Buy, buy_price),
+        (&legs[sell], OrderSide::Sell, sell_price),
+    ] {
+        dex::place_order(
+            programs.dex_program.clone(),
+            leg.market.clone(),
+            leg.vault_order.clone(),
+            vault_info.clone(),
+            programs.payer.clone(),
+            programs.system_program.clone(),
+            signer,
+            side,
+            price,
+            amount,
+            order_time,
+        )?;
+    }
+
+    emit!(ArbitrageOrdersPlaced {
+        vault: vault.key(),
+        buy_market: config.markets[buy],
+        sell_market: config.markets[sell],
+        buy_order: legs[buy].vault_order.key(),
+        sell_order: legs[sell].vault_order.key(),
+        amount,
+        buy_price,
+        sell_price,
+        profit,
+        timestamp: now,
+    });
+    msg!(
+        "Arbitrage orders for {} at {} / {} lock in {} profit",
+        amount,
+        buy_price,
+        sell_price,
+        profit
+    );
+    Ok(Some(profit))
+}
+
+fn load<T: AccountDeserialize + Owner>(info: &AccountInfo) -> Result<T> {
+    require_keys_eq!(
+        *info.owner,
+        T::owner(),
+        VaultError::InvalidArbitrageAccounts
+    );
+    T::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
@@ -0,0 +1,190 @@
+use super::weighted_targets;
+use crate::nav::{self, ACCOUNTS_PER_HOLDING};
+use crate::{dex, AdapterAllocation, HedgeOrder, HedgeUpdated, Vault, VaultError};
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+use chronos_dex::{Market, Order, OrderSide, OrderStatus};
+
+/// Share of assets deployed to adapters; the rest is held back as hedge collateral
+pub const DEPLOYED_BPS: u16 = 5_000;
+/// Number of remaining accounts the caller supplies per hedged holding
+pub const ACCOUNTS_PER_HEDGE: usize = 3;
+
+/// Deploy half of the vault by weight and keep the other half for hedging
+pub fn target_allocations(adapters: &[AdapterAllocation], total_assets: u64) -> Result<Vec<u64>> {
+    weighted_targets(adapters, total_assets, DEPLOYED_BPS)
+}
+
+/// Gross exposure of the vault: its long positions, assets deployed to adapters and other
+/// tokens held, plus the short notional of its hedge orders
+pub fn gross_exposure(vault: &Vault) -> u64 {
+    vault
+        .hedge_orders
+        .iter()
+        .map(|o| (o.amount as u128 * o.price as u128 / dex::PRICE_SCALE as u128) as u64)
+        .fold(
+            vault.deployed_assets.saturating_add(vault.holdings_value),
+            u64::saturating_add,
+        )
+}
+
+/// Programs and payer needed to place and cancel hedge orders
+pub struct HedgePrograms<'info> {
+    pub dex_program: AccountInfo<'info>,
+    pub payer: AccountInfo<'info>,
+    pub system_program: AccountInfo<'info>,
+}
+
+/// Offset the vault's long exposure to each holding that has a hedge market with a sell
+/// order for the holding's full balance at the oracle price. Open tracked orders that no
+/// longer match are cancelled and replaced, at most once per DEX batch.
+/// `holding_accounts` are the holding accounts already checked by `nav::update_holdings_value`.
+/// `hedge_accounts`: for each hedged holding, in registry order, its hedge market, its tracked
+/// hedge order (any account if it has none) and the vault's order PDA for the market's current
+/// batch.
+pub fn hedge<'info>(
+    vault: &mut Account<'info, Vault>,
+    holding_accounts: &[AccountInfo<'info>],
+    hedge_accounts: &[AccountInfo<'info>],
+    programs: &HedgePrograms<'info>,
+    now: i64,
+) -> Result<()> {
+    let hedged: Vec<usize> = (0..vault.holdings.len())
+        .filter(|&i| vault.holdings[i].hedge_market.is_some())
+        .collect();
+    require!(
+        hedge_accounts.len() == hedged.len() * ACCOUNTS_PER_HEDGE,
+        VaultError::InvalidHedgeAccounts
+    );
+
+    let creator = vault.creator;
+    let vault_id = vault.vault_id.to_le_bytes();
+    let bump = [vault.bump];
+    let seeds: &[&[u8]] = &[b"vault", creator.as_ref(), &vault_id, &bump];
+    let signer = &[seeds];
+
+    for (&i, accounts) in hedged.iter().zip(hedge_accounts.chunks(ACCOUNTS_PER_HEDGE)) {
+        let holding = vault.holdings[i];
+        let market_key = accounts[0].key();
+        require!(
+            holding.hedge_market == Some(market_key),
+            VaultError::InvalidHedgeAccounts
+        );
+        let market = load_market(&accounts[0])?;
+
+        let holding_infos = &holding_accounts[i * ACCOUNTS_PER_HOLDING..];
+        let balance =
+            TokenAccount::try_deserialize(&mut &holding_infos[0].try_borrow_data()?[..])?.amount;
+        let oracle_price = nav::read_price(&holding_infos[1], now)?;
+        let price = nav::holding_value(
+            dex::PRICE_SCALE,
+            &oracle_price,
+            holding.decimals,
+            vault.asset_decimals,
+        )?;
+
+        let mut cancelled_order = None;
+        if let Some(index) = vault
+            .hedge_orders
+            .iter()
+            .position(|o| o.holding_mint == holding.mint)
+        {
+            let tracked = vault.hedge_orders[index];
+            require_keys_eq!(
+                accounts[1].key(),
+                tracked.order,
+                VaultError::InvalidHedgeAccounts
+            );
+            let order = load_order(&accounts[1])?;
+            let open = order.status == OrderStatus::Open;
+
+            if open && order.amount == balance && order.price == price {
+                continue;
+            }
+            // The DEX allows one order per trader per batch
+            if order.batch_id == market.current_batch_id {
+                msg!(
+                    "Hedge for {} already placed in batch {}",
+                    holding.mint,
+                    order.batch_id
+                );
+                continue;
+            }
+            // Partially filled orders cannot be cancelled; keep tracking until they fill
+            if order.status == OrderStatus::PartiallyFilled {
+                msg!("Hedge order {} is partially filled", tracked.order);
+                continue;
+            }
+
+            if open {
+                dex::cancel_order(
+                    programs.dex_program.clone(),
+                    accounts[1].clone(),
+                    vault.to_account_info(),
+                    signer,
+                )?;
+            }
+            vault.hedge_orders.remove(index);
+            cancelled_order = Some(tracked.order);
+        }
+
+        let mut placed = None;
+        if balance > 0 {
+            dex::place_order(
+                programs.dex_program.clone(),
+                accounts[0].clone(),
+                accounts[2].clone(),
+                vault.to_account_info(),
+                programs.payer.clone(),
+                programs.system_program.clone(),
+                signer,
+                OrderSide::Sell,
+                price,
+                balance,
+                now,
+            )?;
+            vault.hedge_orders.push(HedgeOrder {
+                holding_mint: holding.mint,
+                market: market_key,
+                order: accounts[2].key(),
+                side: OrderSide::Sell,
+                price,
+                amount: balance,
+                batch_id: market.current_batch_id,
+            });
+            placed = Some(accounts[2].key());
+        }
+
+        emit!(HedgeUpdated {
+            vault: vault.key(),
+            holding_mint: holding.mint,
+            market: market_key,
+            order: placed,
+            cancelled_order,
+            amount: balance,
+            price,
+            timestamp: now,
+        });
+        msg!("Hedged {} of {} at {}", balance, holding.mint, price);
+    }
+
+    Ok(())
+}
+
+fn load_market(info: &AccountInfo) -> Result<Market> {
+    require_keys_eq!(
+        *info.owner,
+        chronos_dex::ID,
+        VaultError::InvalidHedgeAccounts
+    );
+    Market::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
+
+fn load_order(info: &AccountInfo) -> Result<Order> {
+    require_keys_eq!(
+        *info.owner,
+        chronos_dex::ID,
+        VaultError::InvalidHedgeAccounts
+    );
+    Order::try_deserialize(&mut &info.try_borrow_data()?[..])
+}
@@ -0,0 +1,277 @@
+pub mod allocator;
+pub mod arbitrage;
+pub mod delta_neutral;
+pub mod yield_optimization;
+
+use crate::adapter::{self, AdapterAccounts, VaultAccounts, ACCOUNTS_PER_ADAPTER};
+use crate::allocator::ACCOUNTS_PER_CHILD;
+use crate::nav::ACCOUNTS_PER_HOLDING;
+use crate::{AdapterAllocation, ChildVault, StrategyType, Vault, VaultError};
+use anchor_lang::prelude::*;
+use anchor_spl::token::TokenAccount;
+
+/// Target amount per registered adapter for the vault's strategy
+pub fn target_allocations(vault: &Vault, total_assets: u64) -> Result<Vec<u64>> {
+    match vault.strategy_type {
+        StrategyType::YieldOptimization => {
+            yield_optimization::target_allocations(&vault.adapters, total_assets)
+        }
+        StrategyType::DeltaNeutral => {
+            delta_neutral::target_allocations(&vault.adapters, total_assets)
+        }
+        StrategyType::Arbitrage => arbitrage::target_allocations(&vault.adapters, total_assets),
+        // Allocators invest through child vaults, so adapters are drained
+        StrategyType::Allocator => Ok(vec![0; vault.adapters.len()]),
+    }
+}
+
+/// Anything that receives a weighted share of the vault's assets
+pub trait Weighted {
+    fn weight(&self) -> u16;
+}
+
+impl Weighted for AdapterAllocation {
+    fn weight(&self) -> u16 {
+        self.weight
+    }
+}
+
+impl Weighted for ChildVault {
+    fn weight(&self) -> u16 {
+        self.weight
+    }
+}
+
+/// Split `deployed_bps` of `total_assets` across targets in proportion to their weights
+pub fn weighted_targets<T: Weighted>(
+    targets: &[T],
+    total_assets: u64,
+    deployed_bps: u16,
+) -> Result<Vec<u64>> {
+    let total_weight: u128 = targets.iter().map(|t| t.weight() as u128).sum();
+    if total_weight == 0 {
+        return Ok(vec![0; targets.len()]);
+    }
+
+    let deployable = (total_assets as u128)
+        .checked_mul(deployed_bps as u128)
+        .ok_or(error!(VaultError::MathOverflow))?
+        / 10_000;
+
+    targets
+        .iter()
+        .map(|t| {
+            let target = deployable
+                .checked_mul(t.weight() as u128)
+                .ok_or(error!(VaultError::MathOverflow))?
+                / total_weight;
+            u64::try_from(target).map_err(|_| error!(VaultError::MathOverflow))
+        })
+        .collect()
+}
+
+/// Remaining accounts of a strategy instruction, split by what they belong to
+pub struct RemainingAccounts<'a, 'info> {
+    pub adapters: &'a [AccountInfo<'info>],
+    pub children: &'a [AccountInfo<'info>],
+    pub holdings: &'a [AccountInfo<'info>],
+    /// chronos_dex accounts: hedges for DeltaNeutral vaults, trade legs for Arbitrage vaults
+    pub dex: &'a [AccountInfo<'info>],
+}
+
+/// Split remaining accounts into the adapter accounts, the child vault accounts, the
+/// holding accounts and the chronos_dex accounts that follow
+pub fn split_remaining_accounts<'a, 'info>(
+    vault: &Vault,
+    remaining_accounts: &'a [AccountInfo<'info>],
+) -> RemainingAccounts<'a, 'info> {
+    let adapter_count = (vault.adapters.len() * ACCOUNTS_PER_ADAPTER).min(remaining_accounts.len());
+    let (adapters, rest) = remaining_accounts.split_at(adapter_count);
+    let child_count = (vault.children.len() * ACCOUNTS_PER_CHILD).min(rest.len());
+    let (children, rest) = rest.split_at(child_count);
+    let holding_count = (vault.holdings.len() * ACCOUNTS_PER_HOLDING).min(rest.len());
+    let (holdings, dex) = rest.split_at(holding_count);
+    RemainingAccounts {
+        adapters,
+        children,
+        holdings,
+        dex,
+    }
+}
+
+/// Parse the per-adapter remaining accounts, checking them against the vault's registry
+pub fn adapter_accounts<'info>(
+    vault: &Vault,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<Vec<AdapterAccounts<'info>>> {
+    require!(
+        remaining_accounts.len() == vault.adapters.len() * ACCOUNTS_PER_ADAPTER,
+        VaultError::InvalidAdapterAccounts
+    );
+
+    vault
+        .adapters
+        .iter()
+        .zip(remaining_accounts.chunks(ACCOUNTS_PER_ADAPTER))
+        .map(|(allocation, accounts)| {
+            require_keys_eq!(
+                accounts[0].key(),
+                allocation.program_id,
+                VaultError::InvalidAdapterAccounts
+            );
+            require_keys_eq!(
+                accounts[1].key(),
+                allocation.position,
+                VaultError::InvalidAdapterAccounts
+            );
+            Ok(AdapterAccounts {
+                program: accounts[0].clone(),
+                position: accounts[1].clone(),
+                token_account: accounts[2].clone(),
+            })
+        })
+        .collect()
+}
+
+/// Mark each adapter's deployed amount to the balance it reports, so that yield earned
+/// (or losses taken) since the last sync count toward the vault's NAV
+pub fn sync_adapter_balances<'info>(
+    vault: &mut Account<'info, Vault>,
+    vault_token_account: &Account<'info, TokenAccount>,
+    token_program: &AccountInfo<'info>,
+    adapters: &[AdapterAccounts<'info>],
+) -> Result<()> {
+    let creator = vault.creator;
+    let vault_id = vault.vault_id.to_le_bytes();
+    let bump = [vault.bump];
+    let seeds: &[&[u8]] = &[b"vault", creator.as_ref(), &vault_id, &bump];
+    let signer = &[seeds];
+
+    let vault_accounts = VaultAccounts {
+        vault: vault.to_account_info(),
+        vault_token_account: vault_token_account.to_account_info(),
+        token_program: token_program.clone(),
+    };
+
+    let mut deployed_assets: u64 = 0;
+    for (i, accounts) in adapters.iter().enumerate() {
+        let balance = adapter::report_balance(&vault_accounts, accounts, signer)?;
+        if balance != vault.adapters[i].deployed {
+            msg!(
+                "Adapter {} balance moved from {} to {}",
+                vault.adapters[i].program_id,
+                vault.adapters[i].deployed,
+                balance
+            );
+        }
+        vault.adapters[i].deployed = balance;
+        deployed_assets = deployed_assets
+            .checked_add(balance)
+            .ok_or(error!(VaultError::MathOverflow))?;
+    }
+
+    vault.deployed_assets = deployed_assets;
+    Ok(())
+}
+
+/// Move funds between the vault and its adapters toward the strategy's targets.
+/// Withdrawals run first so that freed liquidity can fund deposits in the same pass.
+pub fn rebalance<'info>(
+    vault: &mut Account<'info, Vault>,
+    vault_token_account: &mut Account<'info, TokenAccount>,
+    token_program: &AccountInfo<'info>,
+    adapters: &[AdapterAccounts<'info>],
+) -> Result<()> {
+    let total_assets = vault.total_assets(vault_token_account.amount)?;
+    let targets = target_allocations(vault, total_assets)?;
+
+    let creator = vault.creator;
+    let vault_id = vault.vault_id.to_le_bytes();
+    let bump = [vault.bump];
+    let seeds: &[&[u8]] = &[b"vault", creator.as_ref(), &vault_id, &bump];
+    let signer = &[seeds];
+
+    let vault_accounts = VaultAccounts {
+        vault: vault.to_account_info(),
+        vault_token_account: vault_token_account.to_account_info(),
+        token_program: token_program.clone(),
+    };
+
+    for (i, accounts) in adapters.iter().enumerate() {
+        let deployed = vault.adapters[i].deployed;
+        if deployed > targets[i] {
+            let amount = deployed - targets[i];
+            adapter::withdraw(&vault_accounts, accounts, signer, amount)?;
+            vault.adapters[i].deployed = targets[i];
+            vault.deployed_assets = vault
+                .deployed_assets
+                .checked_sub(amount)
+                .ok_or(error!(VaultError::MathOverflow))?;
+        }
+    }
+
+    vault_token_account.reload()?;
+
+    for (i, accounts) in adapters.iter().enumerate() {
+        let deployed = vault.adapters[i].deployed;
+        if deployed < targets[i] {
+            let amount =
+                (targets[i] - deployed).min(vault.available_liquidity(vault_token_account.amount));
+            if amount == 0 {
+                continue;
+            }
+            adapter::deposit(&vault_accounts, accounts, signer, amount)?;
+            vault_token_account.reload()?;
+            vault.adapters[i].deployed = deployed
+                .checked_add(amount)
+                .ok_or(error!(VaultError::MathOverflow))?;
+            vault.deployed_assets = vault
+                .deployed_assets
+                .checked_add(amount)
+                .ok_or(error!(VaultError::MathOverflow))?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Withdraw everything deployed to the vault's adapters, returning the amount recovered
+pub fn unwind<'info>(
+    vault: &mut Account<'info, Vault>,
+    vault_token_account: &mut Account<'info, TokenAccount>,
+    token_program: &AccountInfo<'info>,
+    adapters: &[AdapterAccounts<'info>],
+) -> Result<u64> {
+    let creator = vault.creator;
+    let vault_id = vault.vault_id.to_le_bytes();
+    let bump = [vault.bump];
+    let seeds: &[&[u8]] = &[b"vault", creator.as_ref(), &vault_id, &bump];
+    let signer = &[seeds];
+
+    let vault_accounts = VaultAccounts {
+        vault: vault.to_account_info(),
+        vault_token_account: vault_token_account.to_account_info(),
+        token_program: token_program.clone(),
+    };
+
+    let mut recovered: u64 = 0;
+    for (i, accounts) in adapters.iter().enumerate() {
+        let amount = vault.adapters[i].deployed;
+        if amount == 0 {
+            continue;
+        }
+        adapter::withdraw(&vault_accounts, accounts, signer, amount)?;
+        vault.adapters[i].deployed = 0;
+        recovered = recovered
+            .checked_add(amount)
+            .ok_or(error!(VaultError::MathOverflow))?;
+    }
+
+    vault.deployed_assets = vault
+        .deployed_assets
+        .checked_sub(recovered)
+        .ok_or(error!(VaultError::MathOverflow))?;
+    vault_token_account.reload()?;
+
+    Ok(recovered)
+}
@@ -0,0 +1,11 @@
+use super::weighted_targets;
+use crate::AdapterAllocation;
+use anchor_lang::prelude::*;
+
+/// Share of assets deployed to adapters; the rest stays liquid for withdrawals
+pub const DEPLOYED_BPS: u16 = 9_000;
+
+/// Deploy most of the vault across yield adapters by weight
+pub fn target_allocations(adapters: &[AdapterAllocation], total_assets: u64) -> Result<Vec<u64>> {
+    weighted_targets(adapters, total_assets, DEPLOYED_BPS)
+}
@@ -6,12 +6,18 @@
 
 import { AnchorProvider, Program, web3, BN } from '@coral-xyz/anchor';
 import { PublicKey, SystemProgram, Transaction } from '@solana/web3.js';
+import {
+  ASSOCIATED_TOKEN_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  getAssociatedTokenAddressSync,
+} from '@solana/spl-token';
 import { getRaikuSDK, ReservationType } from './raiku-mock';
 
 // Types
 
 export interface VaultConfig {
-  strategyType: 'YieldOptimization' | 'DeltaNeutral' | 'Arbitrage';
+  assetMint: PublicKey; // token the vault accepts and accounts in
+  strategyType: 'YieldOptimization' | 'DeltaNeutral' | 'Arbitrage' | 'Allocator';
   riskLevel: number; // 1-10
   rebalanceFrequency: number; // seconds
 }
@@ -44,27 +50,62 @@ export class ChronosClient {
 
   // Vault Operations
 
-  async createVault(config: VaultConfig): Promise<string> {
+  async createVault(config: VaultConfig, vaultId: number = 0): Promise<string> {
     if (!this.vaultProgram) {
       throw new Error('Vault program not initialized');
     }
 
     const [vaultPda] = PublicKey.findProgramAddressSync(
-      [Buffer.from('vault'), this.provider.wallet.publicKey.toBuffer()],
+      [
+        Buffer.from('vault'),
+        this.provider.wallet.publicKey.toBuffer(),
+        new BN(vaultId).toArrayLike(Buffer, 'le', 8),
+      ],
+      this.vaultProgram.programId
+    );
+    const [registryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('vault_registry')],
+      this.vaultProgram.programId
+    );
+    const [shareMintPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('share_mint'), vaultPda.toBuffer()],
+      this.vaultProgram.programId
+    );
+    const [vaultTokenPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('vault_tokens'), vaultPda.toBuffer()],
+      this.vaultProgram.programId
+    );
+    const [historyPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('history'), vaultPda.toBuffer()],
+      this.vaultProgram.programId
+    );
+    const registry: any = await this.vaultProgram.account.vaultRegistry.fetch(registryPda);
+    const [registryEntryPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('registry_entry'), registry.vaultCount.toArrayLike(Buffer, 'le', 8)],
       this.vaultProgram.programId
     );
 
-    const strategyType = { [config.strategyType.toLowerCase()]: {} };
+    // Anchor encodes enum variants by their camelCase names
+    const variant = config.strategyType[0].toLowerCase() + config.strategyType.slice(1);
+    const strategyType = { [variant]: {} };
     
     const tx = await this.vaultProgram.methods
       .initializeVault(
+        new BN(vaultId),
         strategyType,
         config.riskLevel,
         new BN(config.rebalanceFrequency)
       )
       .accounts({
         vault: vaultPda,
+        registry: registryPda,
+        registryEntry: registryEntryPda,
+        assetMint: config.assetMint,
+        vaultTokenAccount: vaultTokenPda,
+        shareMint: shareMintPda,
+        history: historyPda,
         authority: this.provider.wallet.publicKey,
+        tokenProgram: TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -72,26 +113,52 @@ export class ChronosClient {
     return tx;
   }
 
-  async depositToVault(vaultPda: PublicKey, amount: number): Promise<string> {
+  // Token accounts a deposit or withdrawal moves assets and shares between
+  private async vaultTransferAccounts(vaultPda: PublicKey) {
     if (!this.vaultProgram) {
       throw new Error('Vault program not initialized');
     }
 
+    const user = this.provider.wallet.publicKey;
+    const vault: any = await this.vaultProgram.account.vault.fetch(vaultPda);
     const [userPositionPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from('position'),
-        vaultPda.toBuffer(),
-        this.provider.wallet.publicKey.toBuffer(),
-      ],
+      [Buffer.from('position'), vaultPda.toBuffer(), user.toBuffer()],
       this.vaultProgram.programId
     );
+    const [historyPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('history'), vaultPda.toBuffer()],
+      this.vaultProgram.programId
+    );
+
+    return {
+      vault: vaultPda,
+      userPosition: userPositionPda,
+      user,
+      userTokenAccount: getAssociatedTokenAddressSync(vault.assetMint, user),
+      vaultTokenAccount: vault.vaultTokenAccount,
+      shareMint: vault.shareMint,
+      history: historyPda,
+      userShareAccount: getAssociatedTokenAddressSync(vault.shareMint, user),
+    };
+  }
+
+  async depositToVault(
+    vaultPda: PublicKey,
+    amount: number,
+    minSharesOut: number = 0
+  ): Promise<string> {
+    if (!this.vaultProgram) {
+      throw new Error('Vault program not initialized');
+    }
+
+    const accounts = await this.vaultTransferAccounts(vaultPda);
 
     const tx = await this.vaultProgram.methods
-      .deposit(new BN(amount))
+      .deposit(new BN(amount), new BN(minSharesOut))
       .accounts({
-        vault: vaultPda,
-        userPosition: userPositionPda,
-        user: this.provider.wallet.publicKey,
+        ...accounts,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        associatedTokenProgram: ASSOCIATED_TOKEN_PROGRAM_ID,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -99,26 +166,23 @@ export class ChronosClient {
     return tx;
   }
 
-  async withdrawFromVault(vaultPda: PublicKey, shares: number): Promise<string> {
+  async withdrawFromVault(
+    vaultPda: PublicKey,
+    shares: number,
+    minAssetsOut: number = 0
+  ): Promise<string> {
     if (!this.vaultProgram) {
       throw new Error('Vault program not initialized');
     }
 
-    const [userPositionPda] = PublicKey.findProgramAddressSync(
-      [
-        Buffer.from('position'),
-        vaultPda.toBuffer(),
-        this.provider.wallet.publicKey.toBuffer(),
-      ],
-      this.vaultProgram.programId
-    );
+    const accounts = await this.vaultTransferAccounts(vaultPda);
 
     const tx = await this.vaultProgram.methods
-      .withdraw(new BN(shares))
+      .withdraw(new BN(shares), new BN(minAssetsOut))
       .accounts({
-        vault: vaultPda,
-        userPosition: userPositionPda,
-        user: this.provider.wallet.publicKey,
+        ...accounts,
+        tokenProgram: TOKEN_PROGRAM_ID,
+        systemProgram: SystemProgram.programId,
       })
       .rpc();
 
@@ -128,23 +192,41 @@ export class ChronosClient {
   async reserveVaultExecutionSlot(
     vaultPda: PublicKey,
     slotTime: number,
-    type: ReservationType = ReservationType.AOT
+    type: ReservationType = ReservationType.AOT,
+    priority: number = 5
   ): Promise<string> {
     if (!this.vaultProgram) {
       throw new Error('Vault program not initialized');
     }
+    if (!this.orchestratorProgram) {
+      throw new Error('Orchestrator program not initialized');
+    }
 
     // First reserve with Raiku
     const reservation = await this.raikuSDK.reserveSlot(slotTime, type);
 
-    // Then record on-chain
+    // Then record on-chain through the orchestrator, with the vault as requester
     const reservationType = type === ReservationType.AOT ? { aot: {} } : { jit: {} };
+    const orchestratorProgramId = this.orchestratorProgram.programId;
+
+    const [orchestratorPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('orchestrator')],
+      orchestratorProgramId
+    );
+    const [reservationPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('reservation'), vaultPda.toBuffer(), new BN(slotTime).toArrayLike(Buffer, 'le', 8)],
+      orchestratorProgramId
+    );
     
     const tx = await this.vaultProgram.methods
-      .reserveExecutionSlot(new BN(slotTime), reservationType)
+      .reserveExecutionSlot(new BN(slotTime), reservationType, priority)
       .accounts({
         vault: vaultPda,
         authority: this.provider.wallet.publicKey,
+        orchestrator: orchestratorPda,
+        reservation: reservationPda,
+        orchestratorProgram: orchestratorProgramId,
+        systemProgram: SystemProgram.programId,
       })
       .rpc();
 
@@ -190,6 +272,7 @@ export class ChronosClient {
         market: marketPda,
         order: orderPda,
         trader: this.provider.wallet.publicKey,
+        payer: this.provider.wallet.publicKey,
         systemProgram: SystemProgram.programId,
       })
       .rpc();
@@ -296,6 +379,24 @@ export class ChronosClient {
     return this.vaultProgram.account.userPosition.fetch(positionPda);
   }
 
+  // Trailing share price growth in bps per year, read from the vault's on-chain history
+  async getSharePriceApy(vaultPda: PublicKey, windowSeconds: number): Promise<number> {
+    if (!this.vaultProgram) {
+      throw new Error('Vault program not initialized');
+    }
+
+    const [historyPda] = PublicKey.findProgramAddressSync(
+      [Buffer.from('history'), vaultPda.toBuffer()],
+      this.vaultProgram.programId
+    );
+
+    const apy: BN = await this.vaultProgram.methods
+      .sharePriceApy(new BN(windowSeconds))
+      .accounts({ vault: vaultPda, history: historyPda })
+      .view();
+    return apy.toNumber();
+  }
+
   async getMarketInfo(marketPda: PublicKey): Promise<any> {
     if (!this.dexProgram) {
       throw new Error('DEX program not initialized');
@@ -44,6 +44,8 @@ describe("chronos-dex", () => {
     expect(marketAccount.quoteMint.toString()).to.equal(quoteMint.toString());
     expect(marketAccount.currentBatchId.toNumber()).to.equal(0);
     expect(marketAccount.totalVolume.toNumber()).to.equal(0);
+    expect(marketAccount.lastClearingPrice.toNumber()).to.equal(0);
+    expect(marketAccount.lastClearingAt.toNumber()).to.equal(0);
   });
 
   it("Places a buy order with slot reservation", async () => {
@@ -72,6 +74,7 @@ describe("chronos-dex", () => {
         market: marketPda,
         order: orderPda,
         trader: authority,
+        payer: authority,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
@@ -113,6 +116,7 @@ describe("chronos-dex", () => {
         market: marketPda,
         order: sellOrderPda,
         trader: authority,
+        payer: authority,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
@@ -178,6 +182,7 @@ describe("chronos-dex", () => {
           market: marketPda,
           order: invalidOrderPda,
           trader: authority,
+          payer: authority,
           systemProgram: anchor.web3.SystemProgram.programId,
         })
         .rpc();
@@ -0,0 +1,117 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { ChronosMockLending } from "../target/types/chronos_mock_lending";
+import { createMint, getOrCreateAssociatedTokenAccount, mintTo } from "@solana/spl-token";
+import { expect } from "chai";
+
+describe("chronos-mock-lending", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.ChronosMockLending as Program<ChronosMockLending>;
+
+  const authority = provider.wallet.publicKey;
+  const payer = (provider.wallet as anchor.Wallet).payer;
+  // The wallet stands in for a vault, signing the adapter interface instructions itself
+  const vault = authority;
+
+  let mint: anchor.web3.PublicKey;
+  let vaultTokenAccount: anchor.web3.PublicKey;
+
+  const [position] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("position"), vault.toBuffer()],
+    program.programId
+  );
+  const [reserve] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("reserve"), position.toBuffer()],
+    program.programId
+  );
+
+  const adapterAccounts = () => ({
+    vault,
+    vaultTokenAccount,
+    position,
+    reserve,
+  });
+
+  before(async () => {
+    mint = await createMint(provider.connection, payer, authority, null, 6);
+    vaultTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, mint, authority)
+    ).address;
+    await mintTo(provider.connection, payer, mint, vaultTokenAccount, payer, 10_000_000);
+  });
+
+  it("Opens a lending position", async () => {
+    await program.methods
+      .initializePosition()
+      .accounts({ vault, mint, payer: authority })
+      .rpc();
+
+    const account = await program.account.lendingPosition.fetch(position);
+    expect(account.vault.toBase58()).to.equal(vault.toBase58());
+    expect(account.reserve.toBase58()).to.equal(reserve.toBase58());
+    expect(account.admin.toBase58()).to.equal(authority.toBase58());
+  });
+
+  it("Lends, accrues interest and reports the balance", async () => {
+    await program.methods.deposit(new anchor.BN(4_000_000)).accounts(adapterAccounts()).rpc();
+    await program.methods
+      .accrueInterest(new anchor.BN(200_000))
+      .accounts({ position, reserve, payer: authority, payerTokenAccount: vaultTokenAccount })
+      .rpc();
+
+    const balance = await program.methods.reportBalance().accounts(adapterAccounts()).view();
+    expect(balance.toNumber()).to.equal(4_200_000);
+
+    const account = await program.account.lendingPosition.fetch(position);
+    expect(account.principal.toNumber()).to.equal(4_000_000);
+  });
+
+  it("Writes off part of the reserve", async () => {
+    // Only the position admin may write off
+    const stranger = anchor.web3.Keypair.generate();
+    try {
+      await program.methods
+        .writeOff(new anchor.BN(200_000))
+        .accounts({ position, reserve, admin: stranger.publicKey, recipientTokenAccount: vaultTokenAccount })
+        .signers([stranger])
+        .rpc();
+      expect.fail("Should have thrown error for a write-off by a non-admin");
+    } catch (error) {
+      expect(error.toString()).to.include("Unauthorized");
+    }
+
+    await program.methods
+      .writeOff(new anchor.BN(200_000))
+      .accounts({ position, reserve, admin: authority, recipientTokenAccount: vaultTokenAccount })
+      .rpc();
+
+    const balance = await program.methods.reportBalance().accounts(adapterAccounts()).view();
+    expect(balance.toNumber()).to.equal(4_000_000);
+
+    // Put the written-off tokens back for the withdrawal below
+    await program.methods
+      .accrueInterest(new anchor.BN(200_000))
+      .accounts({ position, reserve, payer: authority, payerTokenAccount: vaultTokenAccount })
+      .rpc();
+  });
+
+  it("Returns principal and interest to the vault", async () => {
+    await program.methods.withdraw(new anchor.BN(4_200_000)).accounts(adapterAccounts()).rpc();
+
+    const reserveBalance = await provider.connection.getTokenAccountBalance(reserve);
+    expect(Number(reserveBalance.value.amount)).to.equal(0);
+    const account = await program.account.lendingPosition.fetch(position);
+    expect(account.principal.toNumber()).to.equal(0);
+  });
+
+  it("Fails to withdraw more than the reserve holds", async () => {
+    try {
+      await program.methods.withdraw(new anchor.BN(1)).accounts(adapterAccounts()).rpc();
+      expect.fail("Should have thrown error for an empty reserve");
+    } catch (error) {
+      expect(error.toString()).to.include("InsufficientReserve");
+    }
+  });
+});
@@ -0,0 +1,57 @@
+import * as anchor from "@coral-xyz/anchor";
+import { Program } from "@coral-xyz/anchor";
+import { ChronosMockOracle } from "../target/types/chronos_mock_oracle";
+import { expect } from "chai";
+
+describe("chronos-mock-oracle", () => {
+  const provider = anchor.AnchorProvider.env();
+  anchor.setProvider(provider);
+
+  const program = anchor.workspace.ChronosMockOracle as Program<ChronosMockOracle>;
+
+  const authority = provider.wallet.publicKey;
+  const priceFeed = anchor.web3.Keypair.generate();
+
+  it("Initializes a price feed", async () => {
+    await program.methods
+      .initializePriceFeed(new anchor.BN(150_000_000), new anchor.BN(50_000), -6)
+      .accounts({
+        priceFeed: priceFeed.publicKey,
+        authority: authority,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .signers([priceFeed])
+      .rpc();
+
+    const feed = await program.account.priceFeed.fetch(priceFeed.publicKey);
+    expect(feed.price.toNumber()).to.equal(150_000_000);
+    expect(feed.expo).to.equal(-6);
+    expect(feed.publishTime.toNumber()).to.be.greaterThan(0);
+  });
+
+  it("Publishes a new price", async () => {
+    await program.methods
+      .setPrice(new anchor.BN(151_000_000), new anchor.BN(60_000))
+      .accounts({ priceFeed: priceFeed.publicKey, authority: authority })
+      .rpc();
+
+    const feed = await program.account.priceFeed.fetch(priceFeed.publicKey);
+    expect(feed.price.toNumber()).to.equal(151_000_000);
+    expect(feed.conf.toNumber()).to.equal(60_000);
+  });
+
+  it("Fails to update a feed without its authority", async () => {
+    const other = anchor.web3.Keypair.generate();
+
+    try {
+      await program.methods
+        .setPrice(new anchor.BN(1), new anchor.BN(0))
+        .accounts({ priceFeed: priceFeed.publicKey, authority: other.publicKey })
+        .signers([other])
+        .rpc();
+      expect.fail("Should have thrown error for a foreign authority");
+    } catch (error) {
+      expect(error).to.exist;
+    }
+  });
+});
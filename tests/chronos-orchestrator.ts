@@ -63,6 +63,7 @@ describe("chronos-orchestrator", () => {
         orchestrator: orchestratorPda,
         reservation: reservationPda,
         requester: authority,
+        payer: authority,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
@@ -99,6 +100,7 @@ describe("chronos-orchestrator", () => {
         orchestrator: orchestratorPda,
         reservation: jitReservationPda,
         requester: authority,
+        payer: authority,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
       .rpc();
@@ -249,6 +251,7 @@ describe("chronos-orchestrator", () => {
           orchestrator: orchestratorPda,
           reservation: invalidReservationPda,
           requester: authority,
+          payer: authority,
           systemProgram: anchor.web3.SystemProgram.programId,
         })
         .rpc();
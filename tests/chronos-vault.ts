@@ -1,6 +1,16 @@
 import * as anchor from "@coral-xyz/anchor";
 import { Program } from "@coral-xyz/anchor";
 import { ChronosVault } from "../target/types/chronos_vault";
+import { ChronosOrchestrator } from "../target/types/chronos_orchestrator";
+import { ChronosMockOracle } from "../target/types/chronos_mock_oracle";
+import { ChronosMockLending } from "../target/types/chronos_mock_lending";
+import { ChronosDex } from "../target/types/chronos_dex";
+import {
+  createMint,
+  getAssociatedTokenAddressSync,
+  getOrCreateAssociatedTokenAccount,
+  mintTo,
+} from "@solana/spl-token";
 import { expect } from "chai";
 
 describe("chronos-vault", () => {
@@ -9,18 +19,221 @@ describe("chronos-vault", () => {
   anchor.setProvider(provider);
 
   const program = anchor.workspace.ChronosVault as Program<ChronosVault>;
+  const orchestratorProgram = anchor.workspace.ChronosOrchestrator as Program<ChronosOrchestrator>;
+  const oracleProgram = anchor.workspace.ChronosMockOracle as Program<ChronosMockOracle>;
+  const lendingProgram = anchor.workspace.ChronosMockLending as Program<ChronosMockLending>;
+  const dexProgram = anchor.workspace.ChronosDex as Program<ChronosDex>;
   
   let vaultPda: anchor.web3.PublicKey;
   let vaultBump: number;
   let userPositionPda: anchor.web3.PublicKey;
   let userPositionBump: number;
+  let shareMintPda: anchor.web3.PublicKey;
+  let userShareAccount: anchor.web3.PublicKey;
+  let vaultTokenAccountPda: anchor.web3.PublicKey;
+  let withdrawalRequestPda: anchor.web3.PublicKey;
+  let assetMint: anchor.web3.PublicKey;
+  let userTokenAccount: anchor.web3.PublicKey;
 
   const authority = provider.wallet.publicKey;
+  const vaultId = new anchor.BN(0);
+  const payer = (provider.wallet as anchor.Wallet).payer;
+
+  const [registryPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("vault_registry")],
+    program.programId
+  );
+
+  const [orchestratorPda] = anchor.web3.PublicKey.findProgramAddressSync(
+    [Buffer.from("orchestrator")],
+    orchestratorProgram.programId
+  );
+
+  const reservationPdaFor = (slotTime: anchor.BN, vault = vaultPda) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("reservation"), vault.toBuffer(), slotTime.toArrayLike(Buffer, "le", 8)],
+      orchestratorProgram.programId
+    )[0];
+
+  const reserveSlot = (slotTime: anchor.BN, reservationType: object, vault = vaultPda) =>
+    program.methods
+      .reserveExecutionSlot(slotTime, reservationType, 5)
+      .accounts({
+        vault: vault,
+        authority: authority,
+        orchestrator: orchestratorPda,
+        reservation: reservationPdaFor(slotTime, vault),
+        orchestratorProgram: orchestratorProgram.programId,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+  const vaultAccountsFor = (id: anchor.BN) => {
+    const [vault] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault"), authority.toBuffer(), id.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const [shareMint] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("share_mint"), vault.toBuffer()],
+      program.programId
+    );
+    const [vaultTokenAccount] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_tokens"), vault.toBuffer()],
+      program.programId
+    );
+    return { vault, shareMint, vaultTokenAccount };
+  };
+
+  // Extra vaults of this creator, rebalancing every second
+  const createVault = async (id: anchor.BN, strategyType: object, riskLevel: number) => {
+    const registry = await program.account.vaultRegistry.fetch(registryPda);
+    const [registryEntry] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("registry_entry"), registry.vaultCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    const accounts = vaultAccountsFor(id);
+    await program.methods
+      .initializeVault(id, strategyType, riskLevel, new anchor.BN(1))
+      .accounts({
+        ...accounts,
+        registry: registryPda,
+        registryEntry,
+        assetMint: assetMint,
+        authority: authority,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    return accounts;
+  };
+
+  // A funded keypair to act as a market authority or trading counterparty
+  const fundedKeypair = async () => {
+    const keypair = anchor.web3.Keypair.generate();
+    await provider.sendAndConfirm(
+      new anchor.web3.Transaction().add(
+        anchor.web3.SystemProgram.transfer({
+          fromPubkey: authority,
+          toPubkey: keypair.publicKey,
+          lamports: anchor.web3.LAMPORTS_PER_SOL / 10,
+        })
+      )
+    );
+    return keypair;
+  };
+
+  // Markets are one per authority, so each gets a funded authority of its own
+  const createMarket = async (baseMint: anchor.web3.PublicKey, quoteMint: anchor.web3.PublicKey) => {
+    const marketAuthority = await fundedKeypair();
+    const [market] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("market"), marketAuthority.publicKey.toBuffer()],
+      dexProgram.programId
+    );
+    await dexProgram.methods
+      .initializeMarket(baseMint, quoteMint)
+      .accounts({ market, authority: marketAuthority.publicKey })
+      .signers([marketAuthority])
+      .rpc();
+    return market;
+  };
+
+  const orderPdaFor = (market: anchor.web3.PublicKey, trader: anchor.web3.PublicKey, batchId: anchor.BN) =>
+    anchor.web3.PublicKey.findProgramAddressSync(
+      [
+        Buffer.from("order"),
+        market.toBuffer(),
+        trader.toBuffer(),
+        batchId.toArrayLike(Buffer, "le", 8),
+      ],
+      dexProgram.programId
+    )[0];
+
+  // Vault program events emitted by a confirmed transaction
+  const eventsIn = async (tx: string) => {
+    const txInfo = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+    return [...parser.parseLogs(txInfo.meta.logMessages)];
+  };
+
+  // An extra vault lending through the mock lending adapter, funded with `amount` by the authority
+  const createLendingVault = async (
+    id: anchor.BN,
+    strategyType: object,
+    riskLevel: number,
+    amount: number
+  ) => {
+    const accounts = await createVault(id, strategyType, riskLevel);
+    const [position] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), accounts.vault.toBuffer()],
+      lendingProgram.programId
+    );
+    const [reserve] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("reserve"), position.toBuffer()],
+      lendingProgram.programId
+    );
+    await lendingProgram.methods
+      .initializePosition()
+      .accounts({ vault: accounts.vault, mint: assetMint, payer: authority })
+      .rpc();
+    await program.methods
+      .addAdapter(lendingProgram.programId, position, 100)
+      .accounts({ vault: accounts.vault, authority: authority })
+      .rpc();
+
+    const [depositorPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), accounts.vault.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const shareAccount = getAssociatedTokenAddressSync(accounts.shareMint, authority);
+    await program.methods
+      .deposit(new anchor.BN(amount), new anchor.BN(0))
+      .accounts({
+        vault: accounts.vault,
+        userPosition: depositorPosition,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: accounts.vaultTokenAccount,
+        shareMint: accounts.shareMint,
+        userShareAccount: shareAccount,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const execute = async () => {
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+      await program.methods
+        .executeStrategy()
+        .accounts({
+          vault: accounts.vault,
+          vaultTokenAccount: accounts.vaultTokenAccount,
+          shareMint: accounts.shareMint,
+          keeper: authority,
+          keeperShareAccount: shareAccount,
+        })
+        .remainingAccounts([
+          { pubkey: lendingProgram.programId, isSigner: false, isWritable: false },
+          { pubkey: position, isSigner: false, isWritable: true },
+          { pubkey: reserve, isSigner: false, isWritable: true },
+        ])
+        .rpc();
+      return program.account.vault.fetch(accounts.vault);
+    };
+    return { ...accounts, position, reserve, execute };
+  };
 
   before(async () => {
+    // Create the vault asset mint and fund the user
+    assetMint = await createMint(provider.connection, payer, authority, null, 6);
+    userTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, assetMint, authority)
+    ).address;
+    await mintTo(provider.connection, payer, assetMint, userTokenAccount, payer, 10_000_000_000);
+
     // Derive PDAs
     [vaultPda, vaultBump] = anchor.web3.PublicKey.findProgramAddressSync(
-      [Buffer.from("vault"), authority.toBuffer()],
+      [Buffer.from("vault"), authority.toBuffer(), vaultId.toArrayLike(Buffer, "le", 8)],
       program.programId
     );
 
@@ -28,6 +241,39 @@ describe("chronos-vault", () => {
       [Buffer.from("position"), vaultPda.toBuffer(), authority.toBuffer()],
       program.programId
     );
+
+    [shareMintPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("share_mint"), vaultPda.toBuffer()],
+      program.programId
+    );
+
+    [vaultTokenAccountPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("vault_tokens"), vaultPda.toBuffer()],
+      program.programId
+    );
+
+    [withdrawalRequestPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("withdrawal"), vaultPda.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+
+    userShareAccount = getAssociatedTokenAddressSync(shareMintPda, authority);
+
+    // The vault registry is a singleton too
+    if ((await provider.connection.getAccountInfo(registryPda)) === null) {
+      await program.methods
+        .initializeRegistry()
+        .accounts({ registry: registryPda, payer: authority })
+        .rpc();
+    }
+
+    // The orchestrator is a singleton and may already exist from another suite
+    if ((await provider.connection.getAccountInfo(orchestratorPda)) === null) {
+      await orchestratorProgram.methods
+        .initialize()
+        .accounts({ orchestrator: orchestratorPda, authority: authority })
+        .rpc();
+    }
   });
 
   it("Initializes a vault", async () => {
@@ -35,10 +281,21 @@ describe("chronos-vault", () => {
     const riskLevel = 5;
     const rebalanceFrequency = new anchor.BN(3600); // 1 hour
 
+    const registry = await program.account.vaultRegistry.fetch(registryPda);
+    const [registryEntryPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("registry_entry"), registry.vaultCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+
     const tx = await program.methods
-      .initializeVault(strategyType, riskLevel, rebalanceFrequency)
+      .initializeVault(vaultId, strategyType, riskLevel, rebalanceFrequency)
       .accounts({
         vault: vaultPda,
+        registry: registryPda,
+        registryEntry: registryEntryPda,
+        assetMint: assetMint,
+        vaultTokenAccount: vaultTokenAccountPda,
+        shareMint: shareMintPda,
         authority: authority,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
@@ -50,33 +307,166 @@ describe("chronos-vault", () => {
     const vaultAccount = await program.account.vault.fetch(vaultPda);
 
     expect(vaultAccount.authority.toString()).to.equal(authority.toString());
+    expect(vaultAccount.assetMint.toString()).to.equal(assetMint.toString());
+    expect(vaultAccount.vaultTokenAccount.toString()).to.equal(vaultTokenAccountPda.toString());
+    expect(vaultAccount.shareMint.toString()).to.equal(shareMintPda.toString());
     expect(vaultAccount.riskLevel).to.equal(riskLevel);
-    expect(vaultAccount.totalDeposits.toNumber()).to.equal(0);
+    expect(vaultAccount.deployedAssets.toNumber()).to.equal(0);
     expect(vaultAccount.totalShares.toNumber()).to.equal(0);
+
+    // The vault is listed in the program-wide registry
+    const entry = await program.account.vaultRegistryEntry.fetch(registryEntryPda);
+    expect(entry.vault.toBase58()).to.equal(vaultPda.toBase58());
+    expect(entry.assetMint.toBase58()).to.equal(assetMint.toBase58());
+    const registryAfter = await program.account.vaultRegistry.fetch(registryPda);
+    expect(registryAfter.vaultCount.toNumber()).to.equal(registry.vaultCount.toNumber() + 1);
+  });
+
+  it("Rejects asset mints with more decimals than the vault supports", async () => {
+    const wideMint = await createMint(provider.connection, payer, authority, null, 19);
+    const registry = await program.account.vaultRegistry.fetch(registryPda);
+    const [registryEntry] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("registry_entry"), registry.vaultCount.toArrayLike(Buffer, "le", 8)],
+      program.programId
+    );
+    try {
+      await program.methods
+        .initializeVault(new anchor.BN(99), { yieldOptimization: {} }, 5, new anchor.BN(3600))
+        .accounts({
+          ...vaultAccountsFor(new anchor.BN(99)),
+          registry: registryPda,
+          registryEntry,
+          assetMint: wideMint,
+          authority: authority,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Should have thrown error for a 19-decimal asset mint");
+    } catch (error) {
+      expect(error.toString()).to.include("UnsupportedAssetDecimals");
+    }
   });
 
   it("Deposits funds into vault", async () => {
     const depositAmount = new anchor.BN(1_000_000_000); // 1 SOL
+    const depositAccounts = {
+      vault: vaultPda,
+      userPosition: userPositionPda,
+      user: authority,
+      userTokenAccount: userTokenAccount,
+      vaultTokenAccount: vaultTokenAccountPda,
+      shareMint: shareMintPda,
+      userShareAccount: userShareAccount,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+
+    // The first deposit must be at least one whole token of the 6-decimal asset
+    try {
+      await program.methods
+        .deposit(new anchor.BN(999_999), new anchor.BN(0))
+        .accounts(depositAccounts)
+        .rpc();
+      expect.fail("Should have thrown error for a first deposit below the minimum");
+    } catch (error) {
+      expect(error.toString()).to.include("DepositTooSmall");
+    }
+
+    const tx = await program.methods
+      .deposit(depositAmount, new anchor.BN(0))
+      .accounts(depositAccounts)
+      .rpc();
+
+    console.log("Deposit transaction:", tx);
 
+    // Fetch accounts
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    const positionAccount = await program.account.userPosition.fetch(userPositionPda);
+    const shareBalance = await provider.connection.getTokenAccountBalance(userShareAccount);
+
+    const vaultBalance = await provider.connection.getTokenAccountBalance(vaultTokenAccountPda);
+
+    expect(Number(vaultBalance.value.amount)).to.equal(depositAmount.toNumber());
+    // First deposit mints against the virtual offsets (1000 virtual shares per virtual asset)
+    expect(vaultAccount.totalShares.toNumber()).to.equal(depositAmount.toNumber() * 1000);
+    expect(Number(shareBalance.value.amount)).to.equal(vaultAccount.totalShares.toNumber());
+    expect(positionAccount.costBasis.toNumber()).to.equal(depositAmount.toNumber());
+    expect(positionAccount.shares.toNumber()).to.equal(vaultAccount.totalShares.toNumber());
+    expect(positionAccount.lastDepositAt.toNumber()).to.be.greaterThan(0);
+  });
+
+  it("Emits a Deposited event", async () => {
+    const amount = new anchor.BN(1_000_000);
     const tx = await program.methods
-      .deposit(depositAmount)
+      .deposit(amount, new anchor.BN(0))
       .accounts({
         vault: vaultPda,
         userPosition: userPositionPda,
         user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: vaultTokenAccountPda,
+        shareMint: shareMintPda,
+        userShareAccount: userShareAccount,
         systemProgram: anchor.web3.SystemProgram.programId,
       })
-      .rpc();
+      .rpc({ commitment: "confirmed" });
 
-    console.log("Deposit transaction:", tx);
+    const txInfo = await provider.connection.getTransaction(tx, {
+      commitment: "confirmed",
+      maxSupportedTransactionVersion: 0,
+    });
+    const parser = new anchor.EventParser(program.programId, new anchor.BorshCoder(program.idl));
+    const events = [...parser.parseLogs(txInfo.meta.logMessages)];
+    const deposited = events.find((e) => e.name === "deposited");
 
-    // Fetch accounts
-    const vaultAccount = await program.account.vault.fetch(vaultPda);
-    const positionAccount = await program.account.userPosition.fetch(userPositionPda);
+    expect(deposited).to.exist;
+    expect(deposited.data.assets.toString()).to.equal(amount.toString());
+    expect(deposited.data.user.toBase58()).to.equal(authority.toBase58());
+    expect(deposited.data.sharePrice.toNumber()).to.be.greaterThan(0);
+  });
+
+  it("Previews deposits and withdrawals", async () => {
+    const amount = new anchor.BN(1_000_000);
+
+    const previewShares = await program.methods
+      .previewDeposit(amount)
+      .accounts({ vault: vaultPda, vaultTokenAccount: vaultTokenAccountPda })
+      .view();
+    const previewAssets = await program.methods
+      .previewWithdraw(previewShares)
+      .accounts({ vault: vaultPda, vaultTokenAccount: vaultTokenAccountPda })
+      .view();
+
+    expect(previewShares.toNumber()).to.be.greaterThan(0);
+    // Round trip never returns more than was deposited
+    expect(previewAssets.toNumber()).to.be.at.most(amount.toNumber());
+  });
+
+  it("Fails to deposit below the minimum shares out", async () => {
+    const amount = new anchor.BN(1_000_000);
+    const previewShares = await program.methods
+      .previewDeposit(amount)
+      .accounts({ vault: vaultPda, vaultTokenAccount: vaultTokenAccountPda })
+      .view();
+
+    try {
+      await program.methods
+        .deposit(amount, previewShares.add(new anchor.BN(1)))
+        .accounts({
+          vault: vaultPda,
+          userPosition: userPositionPda,
+          user: authority,
+          userTokenAccount: userTokenAccount,
+          vaultTokenAccount: vaultTokenAccountPda,
+          shareMint: shareMintPda,
+          userShareAccount: userShareAccount,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
 
-    expect(vaultAccount.totalDeposits.toNumber()).to.equal(depositAmount.toNumber());
-    expect(positionAccount.shares.toNumber()).to.be.greaterThan(0);
-    expect(positionAccount.depositedAmount.toNumber()).to.equal(depositAmount.toNumber());
+      expect.fail("Should have thrown error for slippage");
+    } catch (error) {
+      expect(error.toString()).to.include("SlippageExceeded");
+    }
   });
 
   it("Reserves an execution slot", async () => {
@@ -84,13 +474,7 @@ describe("chronos-vault", () => {
     const slotTime = new anchor.BN(currentTime + 30); // 30 seconds in future
     const reservationType = { aot: {} };
 
-    const tx = await program.methods
-      .reserveExecutionSlot(slotTime, reservationType)
-      .accounts({
-        vault: vaultPda,
-        authority: authority,
-      })
-      .rpc();
+    const tx = await reserveSlot(slotTime, reservationType);
 
     console.log("Reserve slot transaction:", tx);
 
@@ -100,104 +484,2156 @@ describe("chronos-vault", () => {
     
     const reservation = vaultAccount.reservedSlots[0];
     expect(reservation.slotTime.toNumber()).to.equal(slotTime.toNumber());
-  });
+    expect(reservation.reservation.toString()).to.equal(reservationPdaFor(slotTime).toString());
 
-  it("Executes vault strategy", async () => {
-    const tx = await program.methods
-      .executeStrategy()
-      .accounts({
-        vault: vaultPda,
-        authority: authority,
-      })
-      .rpc();
+    // The orchestrator holds the reservation with the vault as requester
+    const orchestratorReservation = await orchestratorProgram.account.slotReservation.fetch(
+      reservationPdaFor(slotTime)
+    );
+    expect(orchestratorReservation.requester.toString()).to.equal(vaultPda.toString());
+  });
 
-    console.log("Execute strategy transaction:", tx);
+  it("Cancels and prunes reservations", async () => {
+    const slotTime = new anchor.BN(Math.floor(Date.now() / 1000) + 120);
+    const reservationPda = reservationPdaFor(slotTime);
 
-    // Fetch vault to verify execution
-    const vaultAccount = await program.account.vault.fetch(vaultPda);
-    expect(vaultAccount.lastRebalance.toNumber()).to.be.greaterThan(0);
-  });
+    await reserveSlot(slotTime, { jit: {} });
 
-  it("Withdraws funds from vault", async () => {
-    // First get current position
-    const positionBefore = await program.account.userPosition.fetch(userPositionPda);
-    const sharesToBurn = positionBefore.shares.div(new anchor.BN(2)); // Withdraw 50%
+    const orchestratorReservation = await orchestratorProgram.account.slotReservation.fetch(reservationPda);
+    await orchestratorProgram.methods
+      .confirmSlotReservation(orchestratorReservation.raikuConfirmationId)
+      .accounts({ reservation: reservationPda })
+      .rpc();
 
-    const tx = await program.methods
-      .withdraw(sharesToBurn)
+    await program.methods
+      .cancelReservation()
       .accounts({
         vault: vaultPda,
-        userPosition: userPositionPda,
-        user: authority,
+        authority: authority,
+        reservation: reservationPda,
+        orchestratorProgram: orchestratorProgram.programId,
       })
       .rpc();
+    await program.methods
+      .pruneReservations()
+      .accounts({ vault: vaultPda, orchestratorProgram: orchestratorProgram.programId })
+      .rpc();
 
-    console.log("Withdraw transaction:", tx);
-
-    // Fetch accounts after withdrawal
-    const vaultAfter = await program.account.vault.fetch(vaultPda);
-    const positionAfter = await program.account.userPosition.fetch(userPositionPda);
-
-    expect(positionAfter.shares.toNumber()).to.be.lessThan(positionBefore.shares.toNumber());
-    expect(vaultAfter.totalShares.toNumber()).to.be.lessThan(positionBefore.shares.toNumber());
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.reservedSlots.some((r) => r.slotTime.eq(slotTime))).to.be.false;
+    expect(await provider.connection.getAccountInfo(reservationPda)).to.be.null;
   });
 
-  it("Fails to reserve slot in the past", async () => {
-    const pastTime = new anchor.BN(Math.floor(Date.now() / 1000) - 100);
-    const reservationType = { aot: {} };
+  it("Rejects execution outside a reserved window in reserved-slot mode", async () => {
+    await program.methods
+      .setExecutionMode({ reservedSlot: {} }, new anchor.BN(30))
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
 
     try {
       await program.methods
-        .reserveExecutionSlot(pastTime, reservationType)
+        .executeStrategy()
         .accounts({
           vault: vaultPda,
-          authority: authority,
+          vaultTokenAccount: vaultTokenAccountPda,
+          shareMint: shareMintPda,
+          keeper: authority,
+          keeperShareAccount: userShareAccount,
         })
         .rpc();
-      
-      expect.fail("Should have thrown error for past slot time");
+
+      expect.fail("Should have thrown error for execution outside window");
     } catch (error) {
-      expect(error.toString()).to.include("InvalidSlotTime");
+      expect(error.toString()).to.include("ExecutionOutsideReservedWindow");
     }
+
+    await program.methods
+      .setExecutionMode({ interval: {} }, new anchor.BN(30))
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
   });
 
-  it("Fails to withdraw more shares than owned", async () => {
-    const position = await program.account.userPosition.fetch(userPositionPda);
-    const tooManyShares = position.shares.add(new anchor.BN(1_000_000));
+  it("Consumes a reservation on execution and records missed ones as failed", async () => {
+    const slotVault = await createVault(new anchor.BN(14), { yieldOptimization: {} }, 5);
+    const shareAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, slotVault.shareMint, authority)
+    ).address;
+    await program.methods
+      .setExecutionMode({ reservedSlot: {} }, new anchor.BN(5))
+      .accounts({ vault: slotVault.vault, authority: authority })
+      .rpc();
+
+    // A confirmed reservation is executed inside its window and released
+    const slotTime = new anchor.BN(Math.floor(Date.now() / 1000) + 3);
+    const reservationPda = reservationPdaFor(slotTime, slotVault.vault);
+    await reserveSlot(slotTime, { aot: {} }, slotVault.vault);
+    const pending = await orchestratorProgram.account.slotReservation.fetch(reservationPda);
+    await orchestratorProgram.methods
+      .confirmSlotReservation(pending.raikuConfirmationId)
+      .accounts({ reservation: reservationPda })
+      .rpc();
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    await program.methods
+      .executeStrategy()
+      .accounts({
+        vault: slotVault.vault,
+        vaultTokenAccount: slotVault.vaultTokenAccount,
+        shareMint: slotVault.shareMint,
+        keeper: authority,
+        keeperShareAccount: shareAccount,
+        reservation: reservationPda,
+        orchestratorProgram: orchestratorProgram.programId,
+      })
+      .rpc();
+
+    let orchestratorReservation = await orchestratorProgram.account.slotReservation.fetch(reservationPda);
+    expect(orchestratorReservation.status).to.deep.equal({ executed: {} });
+    let vaultAccount = await program.account.vault.fetch(slotVault.vault);
+    expect(vaultAccount.reservedSlots.length).to.equal(0);
+
+    // A reservation whose window lapses unexecuted is marked failed by anyone
+    await program.methods
+      .setExecutionMode({ reservedSlot: {} }, new anchor.BN(1))
+      .accounts({ vault: slotVault.vault, authority: authority })
+      .rpc();
+    const missedSlotTime = new anchor.BN(Math.floor(Date.now() / 1000) + 2);
+    const missedPda = reservationPdaFor(missedSlotTime, slotVault.vault);
+    await reserveSlot(missedSlotTime, { jit: {} }, slotVault.vault);
+    await new Promise((resolve) => setTimeout(resolve, 5000));
+
+    await program.methods
+      .recordMissedReservations()
+      .accounts({ vault: slotVault.vault, orchestratorProgram: orchestratorProgram.programId })
+      .remainingAccounts([{ pubkey: missedPda, isSigner: false, isWritable: true }])
+      .rpc();
+
+    orchestratorReservation = await orchestratorProgram.account.slotReservation.fetch(missedPda);
+    expect(orchestratorReservation.status).to.deep.equal({ failed: {} });
+    vaultAccount = await program.account.vault.fetch(slotVault.vault);
+    expect(vaultAccount.reservedSlots.length).to.equal(0);
+  });
+
+  it("Registers and removes a strategy adapter", async () => {
+    const adapterProgram = anchor.web3.Keypair.generate().publicKey;
+    const adapterPosition = anchor.web3.Keypair.generate().publicKey;
+
+    await program.methods
+      .addAdapter(adapterProgram, adapterPosition, 100)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+
+    let vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.adapters.length).to.equal(1);
+    expect(vaultAccount.adapters[0].weight).to.equal(100);
+
+    await program.methods
+      .removeAdapter(adapterProgram)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+
+    vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.adapters.length).to.equal(0);
+  });
+
+  it("Manages the keeper allowlist", async () => {
+    const keeper = anchor.web3.Keypair.generate().publicKey;
+
+    await program.methods
+      .addKeeper(keeper)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+    await program.methods
+      .setKeeperConfig(new anchor.BN(1_000), new anchor.BN(300))
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+
+    let vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.keepers.map((k) => k.toString())).to.include(keeper.toString());
+    expect(vaultAccount.keeperReward.toNumber()).to.equal(1_000);
+    expect(vaultAccount.keeperGracePeriod.toNumber()).to.equal(300);
+
+    await program.methods
+      .removeKeeper(keeper)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+
+    vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.keepers.length).to.equal(0);
+  });
+
+  it("Timelocks fee changes", async () => {
+    await program.methods
+      .proposeFeeChange(200, 2_000)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.pendingFeeChange.managementFeeBps).to.equal(200);
+    expect(vaultAccount.pendingFeeChange.performanceFeeBps).to.equal(2_000);
 
     try {
       await program.methods
-        .withdraw(tooManyShares)
-        .accounts({
-          vault: vaultPda,
-          userPosition: userPositionPda,
-          user: authority,
-        })
+        .applyFeeChange()
+        .accounts({ vault: vaultPda, vaultTokenAccount: vaultTokenAccountPda })
         .rpc();
-      
-      expect.fail("Should have thrown error for insufficient shares");
+
+      expect.fail("Should have thrown error for timelocked fee change");
     } catch (error) {
-      expect(error.toString()).to.include("InsufficientShares");
+      expect(error.toString()).to.include("FeeChangeTimelocked");
     }
   });
 
-  it("Fails unauthorized strategy execution", async () => {
-    const unauthorizedUser = anchor.web3.Keypair.generate();
+  it("Collects management and performance fees above the high-water mark", async () => {
+    const feeVault = await createVault(new anchor.BN(11), { yieldOptimization: {} }, 5);
+
+    // Without depositors there is nothing to timelock, so the rates apply at once
+    await program.methods
+      .proposeFeeChange(200, 2_000)
+      .accounts({ vault: feeVault.vault, authority: authority })
+      .rpc();
+    let vaultAccount = await program.account.vault.fetch(feeVault.vault);
+    expect(vaultAccount.managementFeeBps).to.equal(200);
+    expect(vaultAccount.performanceFeeBps).to.equal(2_000);
+    expect(vaultAccount.pendingFeeChange).to.be.null;
+
+    const [depositorPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), feeVault.vault.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const shareAccount = getAssociatedTokenAddressSync(feeVault.shareMint, authority);
+    await program.methods
+      .deposit(new anchor.BN(10_000_000), new anchor.BN(0))
+      .accounts({
+        vault: feeVault.vault,
+        userPosition: depositorPosition,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: feeVault.vaultTokenAccount,
+        shareMint: feeVault.shareMint,
+        userShareAccount: shareAccount,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    const before = await program.account.vault.fetch(feeVault.vault);
+    expect(before.totalShares.toNumber()).to.equal(10_000_000_000);
+    expect(before.highWaterMark.toNumber()).to.equal(1_000_000);
+
+    // The vault gains 10%, then time passes before fees are collected
+    await mintTo(provider.connection, payer, assetMint, feeVault.vaultTokenAccount, payer, 1_000_000);
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    await program.methods
+      .collectFees()
+      .accounts({
+        vault: feeVault.vault,
+        vaultTokenAccount: feeVault.vaultTokenAccount,
+        shareMint: feeVault.shareMint,
+        feeRecipientShareAccount: shareAccount,
+      })
+      .rpc();
+    vaultAccount = await program.account.vault.fetch(feeVault.vault);
+
+    // Management fee: 2% a year of the 11_000_000 assets over the elapsed seconds.
+    // Performance fee: 20% of the gain from a share price of 1_000_000 to 1_099_999
+    // on 10_000_000_000 shares, 999_990 assets, so 199_998.
+    const elapsed = BigInt(vaultAccount.lastFeeAccrual.sub(before.lastFeeAccrual).toString());
+    expect(elapsed > BigInt(0)).to.be.true;
+    const managementFee = (BigInt(11_000_000) * BigInt(200) * elapsed) / BigInt(10_000 * 31_536_000);
+    const feeAssets = managementFee + BigInt(199_998);
+    // Fee shares are worth the fee once minted: 185_183_300 when the management fee rounds to zero
+    const feeShares = (feeAssets * BigInt(10_000_001_000)) / (BigInt(11_000_001) - feeAssets);
+    const highWaterMark =
+      (BigInt(11_000_001) * BigInt(1_000_000_000)) / (BigInt(10_000_001_000) + feeShares);
 
+    const shareBalance = await provider.connection.getTokenAccountBalance(shareAccount);
+    expect(BigInt(shareBalance.value.amount)).to.equal(BigInt(10_000_000_000) + feeShares);
+    expect(BigInt(vaultAccount.totalShares.toString())).to.equal(BigInt(10_000_000_000) + feeShares);
+    expect(vaultAccount.accruedFeeShares.toNumber()).to.equal(0);
+    expect(BigInt(vaultAccount.highWaterMark.toString())).to.equal(highWaterMark);
+
+    // Without further gains, the next collection charges no performance fee
     try {
       await program.methods
-        .executeStrategy()
+        .collectFees()
         .accounts({
-          vault: vaultPda,
-          authority: unauthorizedUser.publicKey,
+          vault: feeVault.vault,
+          vaultTokenAccount: feeVault.vaultTokenAccount,
+          shareMint: feeVault.shareMint,
+          feeRecipientShareAccount: shareAccount,
         })
-        .signers([unauthorizedUser])
         .rpc();
-      
-      expect.fail("Should have thrown error for unauthorized access");
+      expect.fail("Should have thrown error with no fees to collect");
     } catch (error) {
-      expect(error).to.exist;
+      expect(error.toString()).to.include("NoFeesToCollect");
     }
   });
-});
 
+  it("Pays a keeper its reward out of the accrued fee shares", async () => {
+    const keeperVault = await createVault(new anchor.BN(12), { yieldOptimization: {} }, 5);
+    const keeper = await fundedKeypair();
+    const keeperShareAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        keeperVault.shareMint,
+        keeper.publicKey
+      )
+    ).address;
+
+    // A 20% performance fee only, so the fee does not depend on elapsed time
+    await program.methods
+      .proposeFeeChange(0, 2_000)
+      .accounts({ vault: keeperVault.vault, authority: authority })
+      .rpc();
+    await program.methods
+      .addKeeper(keeper.publicKey)
+      .accounts({ vault: keeperVault.vault, authority: authority })
+      .rpc();
+    await program.methods
+      .setKeeperConfig(new anchor.BN(50_000), new anchor.BN(300))
+      .accounts({ vault: keeperVault.vault, authority: authority })
+      .rpc();
+
+    const [depositorPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), keeperVault.vault.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const shareAccount = getAssociatedTokenAddressSync(keeperVault.shareMint, authority);
+    await program.methods
+      .deposit(new anchor.BN(10_000_000), new anchor.BN(0))
+      .accounts({
+        vault: keeperVault.vault,
+        userPosition: depositorPosition,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: keeperVault.vaultTokenAccount,
+        shareMint: keeperVault.shareMint,
+        userShareAccount: shareAccount,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // A 10% gain accrues 185_183_300 fee shares when the keeper executes
+    await mintTo(provider.connection, payer, assetMint, keeperVault.vaultTokenAccount, payer, 1_000_000);
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    await program.methods
+      .executeStrategy()
+      .accounts({
+        vault: keeperVault.vault,
+        vaultTokenAccount: keeperVault.vaultTokenAccount,
+        shareMint: keeperVault.shareMint,
+        keeper: keeper.publicKey,
+        keeperShareAccount: keeperShareAccount,
+      })
+      .signers([keeper])
+      .rpc();
+
+    // The keeper's cut is 50_000 assets worth of shares:
+    // 50_000 * (10_000_000_000 + 185_183_300 + 1_000) / 11_000_001
+    const keeperBalance = await provider.connection.getTokenAccountBalance(keeperShareAccount);
+    expect(Number(keeperBalance.value.amount)).to.equal(46_296_288);
+    let vaultAccount = await program.account.vault.fetch(keeperVault.vault);
+    expect(vaultAccount.accruedFeeShares.toNumber()).to.equal(185_183_300 - 46_296_288);
+
+    // The rest of the fee goes to the fee recipient
+    await program.methods
+      .collectFees()
+      .accounts({
+        vault: keeperVault.vault,
+        vaultTokenAccount: keeperVault.vaultTokenAccount,
+        shareMint: keeperVault.shareMint,
+        feeRecipientShareAccount: shareAccount,
+      })
+      .rpc();
+    const recipientBalance = await provider.connection.getTokenAccountBalance(shareAccount);
+    expect(Number(recipientBalance.value.amount)).to.equal(10_000_000_000 + 185_183_300 - 46_296_288);
+    vaultAccount = await program.account.vault.fetch(keeperVault.vault);
+    expect(vaultAccount.totalShares.toNumber()).to.equal(10_000_000_000 + 185_183_300);
+  });
+
+  it("Tracks a position's realized PnL and fees paid", async () => {
+    const pnlVault = await createVault(new anchor.BN(13), { yieldOptimization: {} }, 5);
+    await program.methods
+      .proposeFeeChange(0, 2_000)
+      .accounts({ vault: pnlVault.vault, authority: authority })
+      .rpc();
+
+    // Fee shares go to a separate recipient so they stay out of the depositor's balance
+    const feeRecipient = await fundedKeypair();
+    const feeRecipientShareAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        pnlVault.shareMint,
+        feeRecipient.publicKey
+      )
+    ).address;
+    await program.methods
+      .setFeeRecipient(feeRecipient.publicKey)
+      .accounts({ vault: pnlVault.vault, authority: authority })
+      .rpc();
+
+    const [depositorPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), pnlVault.vault.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const shareAccount = getAssociatedTokenAddressSync(pnlVault.shareMint, authority);
+    const depositTx = await program.methods
+      .deposit(new anchor.BN(10_000_000), new anchor.BN(0))
+      .accounts({
+        vault: pnlVault.vault,
+        userPosition: depositorPosition,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: pnlVault.vaultTokenAccount,
+        shareMint: pnlVault.shareMint,
+        userShareAccount: shareAccount,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc({ commitment: "confirmed" });
+    const deposited = (await eventsIn(depositTx)).find((e) => e.name === "deposited");
+
+    let position = await program.account.userPosition.fetch(depositorPosition);
+    expect(position.shares.toNumber()).to.equal(10_000_000_000);
+    expect(position.costBasis.toNumber()).to.equal(10_000_000);
+    expect(position.realizedPnl.toNumber()).to.equal(0);
+    expect(position.feesPaid.toNumber()).to.equal(0);
+    expect(position.lastDepositAt.toNumber()).to.equal(deposited.data.timestamp.toNumber());
+
+    // After a 10% gain, the withdrawal first charges a 199_998 asset performance fee
+    // (185_183_300 fee shares), then redeems half the shares at the diluted price:
+    // 5_000_000_000 * 11_000_001 / (10_185_183_300 + 1_000) = 5_400_000 for a cost of 5_000_000
+    await mintTo(provider.connection, payer, assetMint, pnlVault.vaultTokenAccount, payer, 1_000_000);
+    await program.methods
+      .withdraw(new anchor.BN(5_000_000_000), new anchor.BN(0))
+      .accounts({
+        vault: pnlVault.vault,
+        userPosition: depositorPosition,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: pnlVault.vaultTokenAccount,
+        shareMint: pnlVault.shareMint,
+        userShareAccount: shareAccount,
+      })
+      .rpc();
+
+    position = await program.account.userPosition.fetch(depositorPosition);
+    expect(position.shares.toNumber()).to.equal(5_000_000_000);
+    expect(position.costBasis.toNumber()).to.equal(5_000_000);
+    expect(position.realizedPnl.toNumber()).to.equal(400_000);
+    expect(position.feesPaid.toNumber()).to.equal(199_998);
+    expect(position.lastDepositAt.toNumber()).to.equal(deposited.data.timestamp.toNumber());
+
+    // Collecting the fee mints the accrued shares but charges the position nothing more
+    await program.methods
+      .collectFees()
+      .accounts({
+        vault: pnlVault.vault,
+        vaultTokenAccount: pnlVault.vaultTokenAccount,
+        shareMint: pnlVault.shareMint,
+        feeRecipientShareAccount: feeRecipientShareAccount,
+      })
+      .rpc();
+    const feeBalance = await provider.connection.getTokenAccountBalance(feeRecipientShareAccount);
+    expect(Number(feeBalance.value.amount)).to.equal(185_183_300);
+
+    position = await program.account.userPosition.fetch(depositorPosition);
+    expect(position.realizedPnl.toNumber()).to.equal(400_000);
+    expect(position.feesPaid.toNumber()).to.equal(199_998);
+    expect(position.lastDepositAt.toNumber()).to.equal(deposited.data.timestamp.toNumber());
+  });
+
+  it("Lets the guardian pause but not unpause deposits", async () => {
+    const guardian = anchor.web3.Keypair.generate();
+    await program.methods
+      .setGuardian(guardian.publicKey)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+
+    await program.methods
+      .setPauseFlags(true, false, false)
+      .accounts({ vault: vaultPda, signer: guardian.publicKey })
+      .signers([guardian])
+      .rpc();
+
+    try {
+      await program.methods
+        .deposit(new anchor.BN(1_000_000), new anchor.BN(0))
+        .accounts({
+          vault: vaultPda,
+          userPosition: userPositionPda,
+          user: authority,
+          userTokenAccount: userTokenAccount,
+          vaultTokenAccount: vaultTokenAccountPda,
+          shareMint: shareMintPda,
+          userShareAccount: userShareAccount,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Should have thrown error for paused deposits");
+    } catch (error) {
+      expect(error.toString()).to.include("DepositsPaused");
+    }
+
+    try {
+      await program.methods
+        .setPauseFlags(false, false, false)
+        .accounts({ vault: vaultPda, signer: guardian.publicKey })
+        .signers([guardian])
+        .rpc();
+      expect.fail("Should have thrown error for guardian unpause");
+    } catch (error) {
+      expect(error.toString()).to.include("GuardianCannotUnpause");
+    }
+
+    await program.methods
+      .setPauseFlags(false, false, false)
+      .accounts({ vault: vaultPda, signer: authority })
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.depositsPaused).to.be.false;
+  });
+
+  it("Hands the vault authority over in two steps", async () => {
+    const newAuthority = anchor.web3.Keypair.generate();
+
+    await program.methods
+      .proposeAuthority(newAuthority.publicKey)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+    await program.methods
+      .acceptAuthority()
+      .accounts({ vault: vaultPda, newAuthority: newAuthority.publicKey })
+      .signers([newAuthority])
+      .rpc();
+
+    let vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.authority.toBase58()).to.equal(newAuthority.publicKey.toBase58());
+    // The vault address stays bound to its creator
+    expect(vaultAccount.creator.toBase58()).to.equal(authority.toBase58());
+
+    // Hand the vault back for the remaining tests
+    await program.methods
+      .proposeAuthority(authority)
+      .accounts({ vault: vaultPda, authority: newAuthority.publicKey })
+      .signers([newAuthority])
+      .rpc();
+    await program.methods
+      .acceptAuthority()
+      .accounts({ vault: vaultPda, newAuthority: authority })
+      .rpc();
+
+    vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.authority.toBase58()).to.equal(authority.toBase58());
+    expect(vaultAccount.pendingAuthority).to.be.null;
+  });
+
+  it("Updates vault config and timelocks strategy changes", async () => {
+    await program.methods
+      .updateVaultConfig(new anchor.BN(1), { deltaNeutral: {} }, null)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.rebalanceFrequency.toNumber()).to.equal(1);
+    expect(vaultAccount.strategyType).to.deep.equal({ yieldOptimization: {} });
+    expect(vaultAccount.pendingStrategyChange.strategyType).to.deep.equal({ deltaNeutral: {} });
+
+    try {
+      const [registryEntryPda] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("registry_entry"), vaultAccount.registryIndex.toArrayLike(Buffer, "le", 8)],
+        program.programId
+      );
+      await program.methods
+        .applyStrategyChange()
+        .accounts({ vault: vaultPda, registryEntry: registryEntryPda })
+        .rpc();
+      expect.fail("Should have thrown error for timelocked strategy change");
+    } catch (error) {
+      expect(error.toString()).to.include("StrategyChangeTimelocked");
+    }
+
+    try {
+      await program.methods
+        .updateVaultConfig(new anchor.BN(0), null, null)
+        .accounts({ vault: vaultPda, authority: authority })
+        .rpc();
+      expect.fail("Should have thrown error for zero rebalance frequency");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidRebalanceFrequency");
+    }
+  });
+
+  it("Sets the rebalance schedule", async () => {
+    await program.methods
+      .setRebalanceSchedule({
+        kind: { dailyAt: { secondsOfDay: 12 * 3600 } },
+        deviationTriggerBps: 500,
+        autoReserve: null,
+      })
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+
+    let vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.schedule.kind).to.deep.equal({ dailyAt: { secondsOfDay: 12 * 3600 } });
+    expect(vaultAccount.schedule.deviationTriggerBps).to.equal(500);
+
+    try {
+      await program.methods
+        .setRebalanceSchedule({
+          kind: { dailyAt: { secondsOfDay: 86_400 } },
+          deviationTriggerBps: 0,
+          autoReserve: null,
+        })
+        .accounts({ vault: vaultPda, authority: authority })
+        .rpc();
+      expect.fail("Should have thrown error for a calendar time outside the day");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidSchedule");
+    }
+
+    // Align to the one-second frequency so later executions stay due
+    await program.methods
+      .setRebalanceSchedule({ kind: { alignedInterval: {} }, deviationTriggerBps: 0, autoReserve: null })
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+
+    vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.schedule.kind).to.deep.equal({ alignedInterval: {} });
+  });
+
+  it("Enforces deposit caps and the allowlist", async () => {
+    const [allowlistEntryPda] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("allowlist"), vaultPda.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const depositAccounts = {
+      vault: vaultPda,
+      userPosition: userPositionPda,
+      user: authority,
+      userTokenAccount: userTokenAccount,
+      vaultTokenAccount: vaultTokenAccountPda,
+      shareMint: shareMintPda,
+      userShareAccount: userShareAccount,
+      allowlistEntry: null,
+      systemProgram: anchor.web3.SystemProgram.programId,
+    };
+
+    // A TVL cap at the current balance leaves no room for further deposits
+    const vaultBalance = await provider.connection.getTokenAccountBalance(vaultTokenAccountPda);
+    await program.methods
+      .setDepositLimits(new anchor.BN(vaultBalance.value.amount), new anchor.BN(0), false)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+    try {
+      await program.methods
+        .deposit(new anchor.BN(1_000_000), new anchor.BN(0))
+        .accounts(depositAccounts)
+        .rpc();
+      expect.fail("Should have thrown error for exceeding the TVL cap");
+    } catch (error) {
+      expect(error.toString()).to.include("DepositCapExceeded");
+    }
+
+    await program.methods
+      .setDepositLimits(new anchor.BN(0), new anchor.BN(0), true)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+    try {
+      await program.methods
+        .deposit(new anchor.BN(1_000_000), new anchor.BN(0))
+        .accounts(depositAccounts)
+        .rpc();
+      expect.fail("Should have thrown error for a depositor off the allowlist");
+    } catch (error) {
+      expect(error.toString()).to.include("NotAllowlisted");
+    }
+
+    await program.methods
+      .addToAllowlist(authority)
+      .accounts({ vault: vaultPda, allowlistEntry: allowlistEntryPda, authority: authority })
+      .rpc();
+    await program.methods
+      .deposit(new anchor.BN(1_000_000), new anchor.BN(0))
+      .accounts({ ...depositAccounts, allowlistEntry: allowlistEntryPda })
+      .rpc();
+
+    await program.methods
+      .setDepositLimits(new anchor.BN(0), new anchor.BN(0), false)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc();
+  });
+
+  it("Values holdings through oracle price accounts", async () => {
+    // A second token held by the vault, priced at 2.5 asset units per unit
+    const holdingMint = await createMint(provider.connection, payer, authority, null, 9);
+    const holdingTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, holdingMint, vaultPda, true)
+    ).address;
+    const priceFeed = anchor.web3.Keypair.generate();
+    await oracleProgram.methods
+      .initializePriceFeed(new anchor.BN(2_500_000), new anchor.BN(1_000), -6)
+      .accounts({ priceFeed: priceFeed.publicKey, authority: authority })
+      .signers([priceFeed])
+      .rpc();
+
+    // Only accounts owned by the oracle program are read as prices
+    try {
+      await program.methods
+        .addHolding()
+        .accounts({
+          vault: vaultPda,
+          authority: authority,
+          holdingMint: holdingMint,
+          holdingTokenAccount: holdingTokenAccount,
+          oracle: holdingTokenAccount,
+        })
+        .rpc();
+      expect.fail("Should have thrown error for an oracle not owned by the oracle program");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidOracleAccount");
+    }
+
+    const addTx = await program.methods
+      .addHolding()
+      .accounts({
+        vault: vaultPda,
+        authority: authority,
+        holdingMint: holdingMint,
+        holdingTokenAccount: holdingTokenAccount,
+        oracle: priceFeed.publicKey,
+      })
+      .rpc({ commitment: "confirmed" });
+
+    const added = (await eventsIn(addTx)).find((e) => e.name === "holdingAdded");
+    expect(added).to.exist;
+    expect(added.data.mint.toBase58()).to.equal(holdingMint.toBase58());
+    expect(added.data.oracle.toBase58()).to.equal(priceFeed.publicKey.toBase58());
+
+    const holdingAccounts = [
+      { pubkey: holdingTokenAccount, isSigner: false, isWritable: false },
+      { pubkey: priceFeed.publicKey, isSigner: false, isWritable: false },
+    ];
+    await program.methods
+      .updateNav()
+      .accounts({ vault: vaultPda, vaultTokenAccount: vaultTokenAccountPda })
+      .remainingAccounts(holdingAccounts)
+      .rpc();
+
+    let vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.holdings.length).to.equal(1);
+    expect(vaultAccount.holdingsValue.toNumber()).to.equal(0);
+    expect(vaultAccount.navUpdatedAt.toNumber()).to.be.greaterThan(0);
+
+    // Stale prices are rejected
+    await oracleProgram.methods
+      .setPublishTime(new anchor.BN(Math.floor(Date.now() / 1000) - 3600))
+      .accounts({ priceFeed: priceFeed.publicKey, authority: authority })
+      .rpc();
+    try {
+      await program.methods
+        .updateNav()
+        .accounts({ vault: vaultPda, vaultTokenAccount: vaultTokenAccountPda })
+        .remainingAccounts(holdingAccounts)
+        .rpc();
+      expect.fail("Should have thrown error for a stale price");
+    } catch (error) {
+      expect(error.toString()).to.include("StaleOraclePrice");
+    }
+
+    // Remove the empty holding so later executions need no oracle accounts
+    const removeTx = await program.methods
+      .removeHolding(holdingMint)
+      .accounts({ vault: vaultPda, authority: authority })
+      .rpc({ commitment: "confirmed" });
+    vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.holdings.length).to.equal(0);
+
+    const removed = (await eventsIn(removeTx)).find((e) => e.name === "holdingRemoved");
+    expect(removed).to.exist;
+    expect(removed.data.mint.toBase58()).to.equal(holdingMint.toBase58());
+  });
+
+  it("Queues a withdrawal request", async () => {
+    const balance = await provider.connection.getTokenAccountBalance(userShareAccount);
+    const sharesToQueue = new anchor.BN(balance.value.amount).div(new anchor.BN(10));
+    const [history] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("history"), vaultPda.toBuffer()],
+      program.programId
+    );
+    const historyBefore = await program.account.shareHistory.fetch(history);
+
+    await program.methods
+      .requestWithdraw(sharesToQueue, new anchor.BN(0))
+      .accounts({
+        vault: vaultPda,
+        userPosition: userPositionPda,
+        withdrawalRequest: withdrawalRequestPda,
+        user: authority,
+        vaultTokenAccount: vaultTokenAccountPda,
+        shareMint: shareMintPda,
+        userShareAccount: userShareAccount,
+      })
+      .rpc();
+
+    const request = await program.account.withdrawalRequest.fetch(withdrawalRequestPda);
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(request.shares.toString()).to.equal(sharesToQueue.toString());
+    expect(request.epoch.toNumber()).to.equal(vaultAccount.withdrawalEpoch.toNumber());
+    expect(vaultAccount.queuedWithdrawalAssets.toString()).to.equal(request.assets.toString());
+
+    // The burn is recorded in the share history like any other withdrawal
+    const historyAfter = await program.account.shareHistory.fetch(history);
+    expect(historyAfter.head).to.equal((historyBefore.head + 1) % 256);
+    const latest = historyAfter.snapshots[historyBefore.head];
+    expect(latest.totalShares.toString()).to.equal(vaultAccount.totalShares.toString());
+
+    // The request cannot be claimed until the next execution processes the queue
+    try {
+      await program.methods
+        .claimWithdraw()
+        .accounts({
+          vault: vaultPda,
+          withdrawalRequest: withdrawalRequestPda,
+          user: authority,
+          userTokenAccount: userTokenAccount,
+          vaultTokenAccount: vaultTokenAccountPda,
+        })
+        .rpc();
+      expect.fail("Should have thrown error for unprocessed withdrawal");
+    } catch (error) {
+      expect(error.toString()).to.include("WithdrawalNotReady");
+    }
+  });
+
+  it("Executes vault strategy", async () => {
+    const tx = await program.methods
+      .executeStrategy()
+      .accounts({
+        vault: vaultPda,
+        vaultTokenAccount: vaultTokenAccountPda,
+        shareMint: shareMintPda,
+        keeper: authority,
+        keeperShareAccount: userShareAccount,
+      })
+      .rpc();
+
+    console.log("Execute strategy transaction:", tx);
+
+    // Fetch vault to verify execution
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.lastRebalance.toNumber()).to.be.greaterThan(0);
+    // With no adapters registered nothing is deployed and NAV is unchanged
+    expect(vaultAccount.lastNavAfter.toNumber()).to.equal(vaultAccount.lastNavBefore.toNumber());
+  });
+
+  it("Claims a processed withdrawal request", async () => {
+    const request = await program.account.withdrawalRequest.fetch(withdrawalRequestPda);
+    const tokensBefore = await provider.connection.getTokenAccountBalance(userTokenAccount);
+
+    await program.methods
+      .claimWithdraw()
+      .accounts({
+        vault: vaultPda,
+        withdrawalRequest: withdrawalRequestPda,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: vaultTokenAccountPda,
+      })
+      .rpc();
+
+    const tokensAfter = await provider.connection.getTokenAccountBalance(userTokenAccount);
+    expect(
+      new anchor.BN(tokensAfter.value.amount).sub(new anchor.BN(tokensBefore.value.amount)).toString()
+    ).to.equal(request.assets.toString());
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.claimableWithdrawalAssets.toNumber()).to.equal(0);
+    expect(await provider.connection.getAccountInfo(withdrawalRequestPda)).to.be.null;
+  });
+
+  it("Withdraws funds from vault", async () => {
+    // First get current share balance
+    const balanceBefore = await provider.connection.getTokenAccountBalance(userShareAccount);
+    const sharesBefore = new anchor.BN(balanceBefore.value.amount);
+    const sharesToBurn = sharesBefore.div(new anchor.BN(2)); // Withdraw 50%
+
+    const tx = await program.methods
+      .withdraw(sharesToBurn, new anchor.BN(0))
+      .accounts({
+        vault: vaultPda,
+        userPosition: userPositionPda,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: vaultTokenAccountPda,
+        shareMint: shareMintPda,
+        userShareAccount: userShareAccount,
+      })
+      .rpc();
+
+    console.log("Withdraw transaction:", tx);
+
+    // Fetch accounts after withdrawal
+    const vaultAfter = await program.account.vault.fetch(vaultPda);
+    const balanceAfter = await provider.connection.getTokenAccountBalance(userShareAccount);
+
+    expect(Number(balanceAfter.value.amount)).to.be.lessThan(sharesBefore.toNumber());
+    expect(vaultAfter.totalShares.toNumber()).to.equal(Number(balanceAfter.value.amount));
+
+    // Average-cost accounting releases basis in proportion to the shares redeemed
+    const positionAfter = await program.account.userPosition.fetch(userPositionPda);
+    expect(positionAfter.shares.toNumber()).to.be.at.most(Number(balanceAfter.value.amount));
+    expect(positionAfter.costBasis.toNumber()).to.be.greaterThan(0);
+  });
+
+  it("Fails to reserve slot in the past", async () => {
+    const pastTime = new anchor.BN(Math.floor(Date.now() / 1000) - 100);
+    const reservationType = { aot: {} };
+
+    try {
+      await reserveSlot(pastTime, reservationType);
+      
+      expect.fail("Should have thrown error for past slot time");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidSlotTime");
+    }
+  });
+
+  it("Fails to withdraw more shares than owned", async () => {
+    const balance = await provider.connection.getTokenAccountBalance(userShareAccount);
+    const tooManyShares = new anchor.BN(balance.value.amount).add(new anchor.BN(1_000_000));
+
+    try {
+      await program.methods
+        .withdraw(tooManyShares, new anchor.BN(0))
+        .accounts({
+          vault: vaultPda,
+          userPosition: userPositionPda,
+          user: authority,
+          userTokenAccount: userTokenAccount,
+          vaultTokenAccount: vaultTokenAccountPda,
+          shareMint: shareMintPda,
+          userShareAccount: userShareAccount,
+        })
+        .rpc();
+      
+      expect.fail("Should have thrown error for insufficient shares");
+    } catch (error) {
+      expect(error.toString()).to.include("InsufficientShares");
+    }
+  });
+
+  it("Fails unauthorized strategy execution", async () => {
+    const unauthorizedUser = anchor.web3.Keypair.generate();
+
+    try {
+      await program.methods
+        .executeStrategy()
+        .accounts({
+          vault: vaultPda,
+          vaultTokenAccount: vaultTokenAccountPda,
+          shareMint: shareMintPda,
+          keeper: unauthorizedUser.publicKey,
+          keeperShareAccount: userShareAccount,
+        })
+        .signers([unauthorizedUser])
+        .rpc();
+      
+      expect.fail("Should have thrown error for unauthorized access");
+    } catch (error) {
+      expect(error).to.exist;
+    }
+  });
+
+  it("Fails to deposit a token of the wrong mint", async () => {
+    const otherMint = await createMint(provider.connection, payer, authority, null, 6);
+    const otherTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, otherMint, authority)
+    ).address;
+    await mintTo(provider.connection, payer, otherMint, otherTokenAccount, payer, 1_000_000);
+
+    try {
+      await program.methods
+        .deposit(new anchor.BN(1_000_000), new anchor.BN(0))
+        .accounts({
+          vault: vaultPda,
+          userPosition: userPositionPda,
+          user: authority,
+          userTokenAccount: otherTokenAccount,
+          vaultTokenAccount: vaultTokenAccountPda,
+          shareMint: shareMintPda,
+          userShareAccount: userShareAccount,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+
+      expect.fail("Should have thrown error for wrong asset mint");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidAssetMint");
+    }
+  });
+
+  it("Allocates an allocator vault's deposits into a child vault", async () => {
+    // A single child holds the whole deployed allocation, which only aggressive profiles allow
+    const parent = await createVault(new anchor.BN(1), { allocator: {} }, 8);
+    const child = await createVault(new anchor.BN(2), { yieldOptimization: {} }, 5);
+    const childShareAccount = getAssociatedTokenAddressSync(child.shareMint, parent.vault, true);
+    const [childPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), child.vault.toBuffer(), parent.vault.toBuffer()],
+      program.programId
+    );
+
+    await program.methods
+      .addChildVault(10_000)
+      .accounts({
+        vault: parent.vault,
+        authority: authority,
+        childVault: child.vault,
+        childShareMint: child.shareMint,
+        childShareAccount,
+        childPosition,
+      })
+      .rpc();
+
+    const [childHistory] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("history"), child.vault.toBuffer()],
+      program.programId
+    );
+    const childAccounts = [
+      child.vault,
+      child.vaultTokenAccount,
+      child.shareMint,
+      childShareAccount,
+      childPosition,
+      childHistory,
+    ].map((pubkey) => ({ pubkey, isSigner: false, isWritable: true }));
+
+    // Child positions are valued into the NAV, which must be fresh before depositing
+    const [parentPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), parent.vault.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const parentShareAccount = getAssociatedTokenAddressSync(parent.shareMint, authority);
+    const depositTx = new anchor.web3.Transaction()
+      .add(
+        await program.methods
+          .updateNav()
+          .accounts({ vault: parent.vault, vaultTokenAccount: parent.vaultTokenAccount })
+          .remainingAccounts(childAccounts)
+          .instruction()
+      )
+      .add(
+        await program.methods
+          .deposit(new anchor.BN(10_000_000), new anchor.BN(0))
+          .accounts({
+            vault: parent.vault,
+            userPosition: parentPosition,
+            user: authority,
+            userTokenAccount: userTokenAccount,
+            vaultTokenAccount: parent.vaultTokenAccount,
+            shareMint: parent.shareMint,
+            userShareAccount: parentShareAccount,
+            systemProgram: anchor.web3.SystemProgram.programId,
+          })
+          .instruction()
+      );
+    await provider.sendAndConfirm(depositTx);
+
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    await program.methods
+      .executeStrategy()
+      .accounts({
+        vault: parent.vault,
+        vaultTokenAccount: parent.vaultTokenAccount,
+        shareMint: parent.shareMint,
+        keeper: authority,
+        keeperShareAccount: parentShareAccount,
+        associatedTokenProgram: anchor.utils.token.ASSOCIATED_PROGRAM_ID,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts(childAccounts)
+      .rpc();
+
+    // 90% of the allocator's assets are deployed into its only child
+    const childBalance = await provider.connection.getTokenAccountBalance(child.vaultTokenAccount);
+    expect(Number(childBalance.value.amount)).to.equal(9_000_000);
+    const parentAccount = await program.account.vault.fetch(parent.vault);
+    expect(parentAccount.children[0].lastValue.toNumber()).to.be.closeTo(9_000_000, 1);
+    expect(parentAccount.childrenValue.toNumber()).to.equal(
+      parentAccount.children[0].lastValue.toNumber()
+    );
+  });
+
+  it("Earns yield through a lending adapter", async () => {
+    // The only adapter holds the whole deployed allocation, which only aggressive profiles allow
+    const lendingVault = await createVault(new anchor.BN(3), { yieldOptimization: {} }, 8);
+    const [position] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), lendingVault.vault.toBuffer()],
+      lendingProgram.programId
+    );
+    const [reserve] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("reserve"), position.toBuffer()],
+      lendingProgram.programId
+    );
+
+    await lendingProgram.methods
+      .initializePosition()
+      .accounts({ vault: lendingVault.vault, mint: assetMint, payer: authority })
+      .rpc();
+    await program.methods
+      .addAdapter(lendingProgram.programId, position, 100)
+      .accounts({ vault: lendingVault.vault, authority: authority })
+      .rpc();
+
+    const [depositorPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), lendingVault.vault.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const shareAccount = getAssociatedTokenAddressSync(lendingVault.shareMint, authority);
+    await program.methods
+      .deposit(new anchor.BN(10_000_000), new anchor.BN(0))
+      .accounts({
+        vault: lendingVault.vault,
+        userPosition: depositorPosition,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: lendingVault.vaultTokenAccount,
+        shareMint: lendingVault.shareMint,
+        userShareAccount: shareAccount,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    const execute = async () => {
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+      await program.methods
+        .executeStrategy()
+        .accounts({
+          vault: lendingVault.vault,
+          vaultTokenAccount: lendingVault.vaultTokenAccount,
+          shareMint: lendingVault.shareMint,
+          keeper: authority,
+          keeperShareAccount: shareAccount,
+        })
+        .remainingAccounts([
+          { pubkey: lendingProgram.programId, isSigner: false, isWritable: false },
+          { pubkey: position, isSigner: false, isWritable: true },
+          { pubkey: reserve, isSigner: false, isWritable: true },
+        ])
+        .rpc();
+      return program.account.vault.fetch(lendingVault.vault);
+    };
+
+    let vaultAccount = await execute();
+    expect(vaultAccount.adapters[0].deployed.toNumber()).to.equal(9_000_000);
+    let reserveBalance = await provider.connection.getTokenAccountBalance(reserve);
+    expect(Number(reserveBalance.value.amount)).to.equal(9_000_000);
+
+    // Interest paid into the reserve is picked up through report_balance on the next execution
+    await lendingProgram.methods
+      .accrueInterest(new anchor.BN(1_000_000))
+      .accounts({
+        position,
+        reserve,
+        payer: authority,
+        payerTokenAccount: userTokenAccount,
+      })
+      .rpc();
+
+    vaultAccount = await execute();
+    expect(vaultAccount.lastNavBefore.toNumber()).to.equal(11_000_000);
+    expect(vaultAccount.adapters[0].deployed.toNumber()).to.equal(9_900_000);
+    reserveBalance = await provider.connection.getTokenAccountBalance(reserve);
+    expect(Number(reserveBalance.value.amount)).to.equal(9_900_000);
+
+    // The deposit and both executions are in the share history, with the interest in the last
+    const [history] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("history"), lendingVault.vault.toBuffer()],
+      program.programId
+    );
+    const historyAccount = await program.account.shareHistory.fetch(history);
+    expect(historyAccount.len).to.equal(3);
+    const [, firstExecution, secondExecution] = historyAccount.snapshots;
+    expect(secondExecution.nav.toNumber()).to.equal(11_000_000);
+    expect(secondExecution.sharePrice.gt(firstExecution.sharePrice)).to.be.true;
+
+    const apy = await program.methods
+      .sharePriceApy(new anchor.BN(1))
+      .accounts({ vault: lendingVault.vault, history })
+      .view();
+    expect(apy.toNumber()).to.be.greaterThan(0);
+
+    try {
+      await program.methods
+        .sharePriceApy(new anchor.BN(365 * 86_400))
+        .accounts({ vault: lendingVault.vault, history })
+        .view();
+      expect.fail("Should have thrown error for a window the history does not cover");
+    } catch (error) {
+      expect(error.toString()).to.include("InsufficientHistory");
+    }
+  });
+
+  it("Hedges a delta-neutral holding with a DEX sell order", async () => {
+    const hedgedVault = await createVault(new anchor.BN(4), { deltaNeutral: {} }, 5);
+    const [depositorPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), hedgedVault.vault.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const shareAccount = getAssociatedTokenAddressSync(hedgedVault.shareMint, authority);
+    await program.methods
+      .deposit(new anchor.BN(10_000_000), new anchor.BN(0))
+      .accounts({
+        vault: hedgedVault.vault,
+        userPosition: depositorPosition,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: hedgedVault.vaultTokenAccount,
+        shareMint: hedgedVault.shareMint,
+        userShareAccount: shareAccount,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // The vault holds 4 units of a token priced at 2.5 asset units
+    const holdingMint = await createMint(provider.connection, payer, authority, null, 9);
+    const holdingTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        holdingMint,
+        hedgedVault.vault,
+        true
+      )
+    ).address;
+    await mintTo(provider.connection, payer, holdingMint, holdingTokenAccount, payer, 4_000_000_000);
+    const priceFeed = anchor.web3.Keypair.generate();
+    await oracleProgram.methods
+      .initializePriceFeed(new anchor.BN(2_500_000), new anchor.BN(1_000), -6)
+      .accounts({ priceFeed: priceFeed.publicKey, authority: authority })
+      .signers([priceFeed])
+      .rpc();
+    await program.methods
+      .addHolding()
+      .accounts({
+        vault: hedgedVault.vault,
+        authority: authority,
+        holdingMint: holdingMint,
+        holdingTokenAccount: holdingTokenAccount,
+        oracle: priceFeed.publicKey,
+      })
+      .rpc();
+
+    const wrongMarket = await createMarket(assetMint, holdingMint);
+    try {
+      await program.methods
+        .setHedgeMarket(holdingMint)
+        .accounts({ vault: hedgedVault.vault, authority: authority, market: wrongMarket })
+        .rpc();
+      expect.fail("Should have thrown error for a market of the wrong pair");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidHedgeMarket");
+    }
+
+    const market = await createMarket(holdingMint, assetMint);
+    await program.methods
+      .setHedgeMarket(holdingMint)
+      .accounts({ vault: hedgedVault.vault, authority: authority, market })
+      .rpc();
+
+    const order = orderPdaFor(market, hedgedVault.vault, new anchor.BN(0));
+
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    await program.methods
+      .executeStrategy()
+      .accounts({
+        vault: hedgedVault.vault,
+        vaultTokenAccount: hedgedVault.vaultTokenAccount,
+        shareMint: hedgedVault.shareMint,
+        keeper: authority,
+        keeperShareAccount: shareAccount,
+        dexProgram: dexProgram.programId,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts([
+        { pubkey: holdingTokenAccount, isSigner: false, isWritable: false },
+        { pubkey: priceFeed.publicKey, isSigner: false, isWritable: false },
+        { pubkey: market, isSigner: false, isWritable: false },
+        // No tracked order yet
+        { pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: order, isSigner: false, isWritable: true },
+      ])
+      .rpc();
+
+    // The full balance is offered at the oracle price: 2.5 asset units per whole token
+    let vaultAccount = await program.account.vault.fetch(hedgedVault.vault);
+    expect(vaultAccount.hedgeOrders.length).to.equal(1);
+    expect(vaultAccount.hedgeOrders[0].order.toString()).to.equal(order.toString());
+    expect(vaultAccount.hedgeOrders[0].amount.toNumber()).to.equal(4_000_000_000);
+    expect(vaultAccount.hedgeOrders[0].price.toNumber()).to.equal(2_500);
+    let orderAccount = await dexProgram.account.order.fetch(order);
+    expect(orderAccount.trader.toString()).to.equal(hedgedVault.vault.toString());
+    expect(orderAccount.side).to.deep.equal({ sell: {} });
+
+    // A holding with a tracked order keeps its hedge market until the order is cancelled
+    try {
+      await program.methods
+        .setHedgeMarket(holdingMint)
+        .accounts({ vault: hedgedVault.vault, authority: authority, market: null })
+        .rpc();
+      expect.fail("Should have thrown error for a tracked hedge order");
+    } catch (error) {
+      expect(error.toString()).to.include("HedgeOrderOpen");
+    }
+
+    await program.methods
+      .cancelHedgeOrder(holdingMint)
+      .accounts({ vault: hedgedVault.vault, authority: authority, order })
+      .rpc();
+    vaultAccount = await program.account.vault.fetch(hedgedVault.vault);
+    expect(vaultAccount.hedgeOrders.length).to.equal(0);
+    orderAccount = await dexProgram.account.order.fetch(order);
+    expect(orderAccount.status).to.deep.equal({ cancelled: {} });
+
+    await program.methods
+      .setHedgeMarket(holdingMint)
+      .accounts({ vault: hedgedVault.vault, authority: authority, market: null })
+      .rpc();
+    vaultAccount = await program.account.vault.fetch(hedgedVault.vault);
+    expect(vaultAccount.holdings[0].hedgeMarket).to.be.null;
+  });
+
+  it("Arbitrages between two DEX markets within a reserved slot", async () => {
+    const arbVault = await createVault(new anchor.BN(5), { arbitrage: {} }, 8);
+    const [depositorPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), arbVault.vault.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const shareAccount = getAssociatedTokenAddressSync(arbVault.shareMint, authority);
+    await program.methods
+      .deposit(new anchor.BN(10_000_000), new anchor.BN(0))
+      .accounts({
+        vault: arbVault.vault,
+        userPosition: depositorPosition,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: arbVault.vaultTokenAccount,
+        shareMint: arbVault.shareMint,
+        userShareAccount: shareAccount,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // The traded token is worth 1 asset unit by its oracle
+    const holdingMint = await createMint(provider.connection, payer, authority, null, 6);
+    const vaultHoldingAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, holdingMint, arbVault.vault, true)
+    ).address;
+    const priceFeed = anchor.web3.Keypair.generate();
+    await oracleProgram.methods
+      .initializePriceFeed(new anchor.BN(1_000_000), new anchor.BN(1_000), -6)
+      .accounts({ priceFeed: priceFeed.publicKey, authority: authority })
+      .signers([priceFeed])
+      .rpc();
+    await program.methods
+      .addHolding()
+      .accounts({
+        vault: arbVault.vault,
+        authority: authority,
+        holdingMint: holdingMint,
+        holdingTokenAccount: vaultHoldingAccount,
+        oracle: priceFeed.publicKey,
+      })
+      .rpc();
+
+    // A counterparty holding both tokens, trading against the authority to set clearing prices
+    const maker = await fundedKeypair();
+    const makerBase = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, holdingMint, maker.publicKey)
+    ).address;
+    const makerQuote = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, assetMint, maker.publicKey)
+    ).address;
+    const authorityBase = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, holdingMint, authority)
+    ).address;
+    await mintTo(provider.connection, payer, holdingMint, makerBase, payer, 10_000_000);
+    await mintTo(provider.connection, payer, holdingMint, authorityBase, payer, 10_000_000);
+    await mintTo(provider.connection, payer, assetMint, makerQuote, payer, 10_000_000);
+
+    const placeOrder = (
+      market: anchor.web3.PublicKey,
+      trader: anchor.web3.Keypair | null,
+      side: object,
+      price: number,
+      amount: number,
+      batchId: anchor.BN
+    ) => {
+      const traderKey = trader ? trader.publicKey : authority;
+      return dexProgram.methods
+        .placeOrder(
+          side,
+          new anchor.BN(price),
+          new anchor.BN(amount),
+          new anchor.BN(Math.floor(Date.now() / 1000) + 10)
+        )
+        .accounts({
+          market,
+          order: orderPdaFor(market, traderKey, batchId),
+          trader: traderKey,
+          payer: traderKey,
+        })
+        .signers(trader ? [trader] : [])
+        .rpc();
+    };
+
+    // Market A last clears at 0.95 and market B at 1.05, then both move on to batch 1
+    const clearAt = async (market: anchor.web3.PublicKey, price: number) => {
+      const batchId = new anchor.BN(0);
+      await placeOrder(market, null, { sell: {} }, price, 1_000_000, batchId);
+      await placeOrder(market, maker, { buy: {} }, price, 1_000_000, batchId);
+      await dexProgram.methods
+        .settleTrade(new anchor.BN(1_000_000), new anchor.BN(price))
+        .accounts({
+          market,
+          buyOrder: orderPdaFor(market, maker.publicKey, batchId),
+          sellOrder: orderPdaFor(market, authority, batchId),
+          buyer: maker.publicKey,
+          seller: authority,
+          buyerBaseAccount: makerBase,
+          buyerQuoteAccount: makerQuote,
+          sellerBaseAccount: authorityBase,
+          sellerQuoteAccount: userTokenAccount,
+        })
+        .signers([maker])
+        .rpc();
+      await dexProgram.methods.executeBatchAuction().accounts({ market }).rpc();
+    };
+    const marketA = await createMarket(holdingMint, assetMint);
+    const marketB = await createMarket(holdingMint, assetMint);
+    await clearAt(marketA, 950_000);
+    await clearAt(marketB, 1_050_000);
+    const marketAccount = await dexProgram.account.market.fetch(marketA);
+    expect(marketAccount.lastClearingPrice.toNumber()).to.equal(950_000);
+    expect(marketAccount.lastClearingAt.toNumber()).to.be.greaterThan(0);
+
+    // The keeper only needs the markets and the vault's order PDAs for batch 1
+    const batchId = new anchor.BN(1);
+    const vaultOrderA = orderPdaFor(marketA, arbVault.vault, batchId);
+    const vaultOrderB = orderPdaFor(marketB, arbVault.vault, batchId);
+    const slotTime = new anchor.BN(Math.floor(Date.now() / 1000) + 3);
+    await reserveSlot(slotTime, { aot: {} }, arbVault.vault);
+    await new Promise((resolve) => setTimeout(resolve, 4000));
+
+    const setRoute = (minProfit: number) =>
+      program.methods
+        .setArbitrageConfig(new anchor.BN(1_000_000), new anchor.BN(minProfit))
+        .accounts({ vault: arbVault.vault, authority: authority, marketA, marketB })
+        .rpc();
+    const execute = () =>
+      program.methods
+        .executeStrategy()
+        .accounts({
+          vault: arbVault.vault,
+          vaultTokenAccount: arbVault.vaultTokenAccount,
+          shareMint: arbVault.shareMint,
+          keeper: authority,
+          keeperShareAccount: shareAccount,
+          reservation: reservationPdaFor(slotTime, arbVault.vault),
+          orchestratorProgram: orchestratorProgram.programId,
+          dexProgram: dexProgram.programId,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts([
+          { pubkey: vaultHoldingAccount, isSigner: false, isWritable: true },
+          { pubkey: priceFeed.publicKey, isSigner: false, isWritable: false },
+          { pubkey: marketA, isSigner: false, isWritable: true },
+          { pubkey: vaultOrderA, isSigner: false, isWritable: true },
+          { pubkey: marketB, isSigner: false, isWritable: true },
+          { pubkey: vaultOrderB, isSigner: false, isWritable: true },
+        ])
+        .rpc();
+
+    // Buying 1 token at 0.95 and selling it at 1.05 locks in 0.1, short of a 0.2 floor
+    await setRoute(200_000);
+    try {
+      await execute();
+      expect.fail("Should have thrown error for profit below the floor");
+    } catch (error) {
+      expect(error.toString()).to.include("ArbitrageProfitBelowFloor");
+    }
+
+    // The vault bids at market A's clearing price and asks at market B's
+    await setRoute(50_000);
+    await execute();
+    let buyOrder = await dexProgram.account.order.fetch(vaultOrderA);
+    expect(buyOrder.side).to.deep.equal({ buy: {} });
+    expect(buyOrder.price.toNumber()).to.equal(950_000);
+    expect(buyOrder.amount.toNumber()).to.equal(1_000_000);
+    expect(buyOrder.slotReservationTime.toNumber()).to.be.at.least(slotTime.toNumber());
+    let sellOrder = await dexProgram.account.order.fetch(vaultOrderB);
+    expect(sellOrder.side).to.deep.equal({ sell: {} });
+    expect(sellOrder.price.toNumber()).to.equal(1_050_000);
+
+    // The maker fills both legs, signing only its own transactions
+    const fill = async (
+      market: anchor.web3.PublicKey,
+      vaultOrder: anchor.web3.PublicKey,
+      side: object,
+      price: number
+    ) => {
+      await placeOrder(market, maker, side, price, 1_000_000, batchId);
+      await program.methods
+        .settleArbitrage(new anchor.BN(1_000_000), new anchor.BN(price))
+        .accounts({
+          vault: arbVault.vault,
+          vaultTokenAccount: arbVault.vaultTokenAccount,
+          holdingTokenAccount: vaultHoldingAccount,
+          oracle: priceFeed.publicKey,
+          market,
+          vaultOrder,
+          counterpartyOrder: orderPdaFor(market, maker.publicKey, batchId),
+          counterparty: maker.publicKey,
+          counterpartyBaseAccount: makerBase,
+          counterpartyQuoteAccount: makerQuote,
+          dexProgram: dexProgram.programId,
+        })
+        .signers([maker])
+        .rpc();
+    };
+    await fill(marketA, vaultOrderA, { sell: {} }, 950_000);
+    let inventory = await provider.connection.getTokenAccountBalance(vaultHoldingAccount);
+    expect(Number(inventory.value.amount)).to.equal(1_000_000);
+    await fill(marketB, vaultOrderB, { buy: {} }, 1_050_000);
+
+    const liquid = await provider.connection.getTokenAccountBalance(arbVault.vaultTokenAccount);
+    expect(Number(liquid.value.amount)).to.equal(10_100_000);
+    inventory = await provider.connection.getTokenAccountBalance(vaultHoldingAccount);
+    expect(Number(inventory.value.amount)).to.equal(0);
+    buyOrder = await dexProgram.account.order.fetch(vaultOrderA);
+    expect(buyOrder.status).to.deep.equal({ filled: {} });
+    sellOrder = await dexProgram.account.order.fetch(vaultOrderB);
+    expect(sellOrder.status).to.deep.equal({ filled: {} });
+  });
+
+  it("Caps each adapter's share of NAV by risk level", async () => {
+    // A moderate yield vault deploys 90% to its only adapter, over the 50% cap
+    const yieldVault = await createLendingVault(
+      new anchor.BN(6),
+      { yieldOptimization: {} },
+      5,
+      10_000_000
+    );
+    try {
+      await yieldVault.execute();
+      expect.fail("Should have thrown error for an adapter over the allocation cap");
+    } catch (error) {
+      expect(error.toString()).to.include("AdapterAllocationExceeded");
+    }
+
+    // A moderate delta-neutral vault deploys 50%, which is within the cap
+    const neutralVault = await createLendingVault(
+      new anchor.BN(7),
+      { deltaNeutral: {} },
+      5,
+      10_000_000
+    );
+    const vaultAccount = await neutralVault.execute();
+    expect(vaultAccount.adapters[0].deployed.toNumber()).to.equal(5_000_000);
+  });
+
+  it("Caps delta-neutral gross exposure by risk level", async () => {
+    const hedgedVault = await createVault(new anchor.BN(8), { deltaNeutral: {} }, 5);
+    const [depositorPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), hedgedVault.vault.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const shareAccount = getAssociatedTokenAddressSync(hedgedVault.shareMint, authority);
+    const deposit = (amount: number) =>
+      program.methods
+        .deposit(new anchor.BN(amount), new anchor.BN(0))
+        .accounts({
+          vault: hedgedVault.vault,
+          userPosition: depositorPosition,
+          user: authority,
+          userTokenAccount: userTokenAccount,
+          vaultTokenAccount: hedgedVault.vaultTokenAccount,
+          shareMint: hedgedVault.shareMint,
+          userShareAccount: shareAccount,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+    await deposit(1_000_000);
+
+    // The vault holds 4 units of a token priced at 2.5 asset units, hedged on a DEX market
+    const holdingMint = await createMint(provider.connection, payer, authority, null, 9);
+    const holdingTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        holdingMint,
+        hedgedVault.vault,
+        true
+      )
+    ).address;
+    await mintTo(provider.connection, payer, holdingMint, holdingTokenAccount, payer, 4_000_000_000);
+    const priceFeed = anchor.web3.Keypair.generate();
+    await oracleProgram.methods
+      .initializePriceFeed(new anchor.BN(2_500_000), new anchor.BN(1_000), -6)
+      .accounts({ priceFeed: priceFeed.publicKey, authority: authority })
+      .signers([priceFeed])
+      .rpc();
+    await program.methods
+      .addHolding()
+      .accounts({
+        vault: hedgedVault.vault,
+        authority: authority,
+        holdingMint: holdingMint,
+        holdingTokenAccount: holdingTokenAccount,
+        oracle: priceFeed.publicKey,
+      })
+      .rpc();
+    const market = await createMarket(holdingMint, assetMint);
+    await program.methods
+      .setHedgeMarket(holdingMint)
+      .accounts({ vault: hedgedVault.vault, authority: authority, market })
+      .rpc();
+
+    const holdingAccounts = [
+      { pubkey: holdingTokenAccount, isSigner: false, isWritable: false },
+      { pubkey: priceFeed.publicKey, isSigner: false, isWritable: false },
+    ];
+    const execute = async () => {
+      await new Promise((resolve) => setTimeout(resolve, 2000));
+      await program.methods
+        .executeStrategy()
+        .accounts({
+          vault: hedgedVault.vault,
+          vaultTokenAccount: hedgedVault.vaultTokenAccount,
+          shareMint: hedgedVault.shareMint,
+          keeper: authority,
+          keeperShareAccount: shareAccount,
+          dexProgram: dexProgram.programId,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .remainingAccounts([
+          ...holdingAccounts,
+          { pubkey: market, isSigner: false, isWritable: false },
+          { pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false },
+          { pubkey: orderPdaFor(market, hedgedVault.vault, new anchor.BN(0)), isSigner: false, isWritable: true },
+        ])
+        .rpc();
+      return program.account.vault.fetch(hedgedVault.vault);
+    };
+
+    // 10 of holdings long and 10 of hedges short is 20 gross on an 11 NAV, over the 1.5x cap
+    try {
+      await execute();
+      expect.fail("Should have thrown error for gross exposure over the leverage cap");
+    } catch (error) {
+      expect(error.toString()).to.include("LeverageExceeded");
+    }
+
+    // With 9 more in cash the same hedge is 20 gross on a 20 NAV
+    await program.methods
+      .updateNav()
+      .accounts({ vault: hedgedVault.vault, vaultTokenAccount: hedgedVault.vaultTokenAccount })
+      .remainingAccounts(holdingAccounts)
+      .rpc();
+    await deposit(9_000_000);
+    const vaultAccount = await execute();
+    expect(vaultAccount.hedgeOrders.length).to.equal(1);
+    expect(vaultAccount.lastNavAfter.toNumber()).to.equal(20_000_000);
+  });
+
+  it("Holds back losses over the drawdown limit until they are acknowledged", async () => {
+    // An aggressive yield vault tolerates a 5% loss per rebalance
+    const lendingVault = await createLendingVault(
+      new anchor.BN(9),
+      { yieldOptimization: {} },
+      8,
+      10_000_000
+    );
+    let vaultAccount = await lendingVault.execute();
+    expect(vaultAccount.adapters[0].deployed.toNumber()).to.equal(9_000_000);
+
+    const writeOff = (amount: number) =>
+      lendingProgram.methods
+        .writeOff(new anchor.BN(amount))
+        .accounts({
+          position: lendingVault.position,
+          reserve: lendingVault.reserve,
+          admin: authority,
+          recipientTokenAccount: userTokenAccount,
+        })
+        .rpc();
+
+    // A 3% loss is marked and the rebalance goes ahead
+    await writeOff(300_000);
+    vaultAccount = await lendingVault.execute();
+    expect(vaultAccount.lastNavAfter.toNumber()).to.equal(9_700_000);
+    expect(vaultAccount.lastSharePrice.toNumber()).to.equal(970_000);
+
+    // A further loss of over 10% is held back
+    await writeOff(1_000_000);
+    try {
+      await lendingVault.execute();
+      expect.fail("Should have thrown error for an unacknowledged loss over the drawdown limit");
+    } catch (error) {
+      expect(error.toString()).to.include("LossNotAcknowledged");
+    }
+
+    // Once the authority acknowledges the loss down to the marked share price of
+    // 8_700_001 * 10^9 / 10_000_001_000, the vault executes again
+    const acknowledge = (sharePrice: number) =>
+      program.methods
+        .acknowledgeLoss(new anchor.BN(sharePrice))
+        .accounts({ vault: lendingVault.vault, signer: authority })
+        .rpc();
+    try {
+      await acknowledge(980_000);
+      expect.fail("Should have thrown error for an acknowledgement above the committed price");
+    } catch (error) {
+      expect(error.toString()).to.include("InvalidLossAcknowledgement");
+    }
+    await acknowledge(870_000);
+    vaultAccount = await lendingVault.execute();
+    expect(vaultAccount.lastNavAfter.toNumber()).to.equal(8_700_000);
+    expect(vaultAccount.lastSharePrice.toNumber()).to.equal(870_000);
+    expect(vaultAccount.adapters[0].deployed.toNumber()).to.equal(7_830_000);
+  });
+
+  it("Settles a hedge order against a counterparty bid", async () => {
+    const hedgedVault = await createVault(new anchor.BN(10), { deltaNeutral: {} }, 5);
+    const [depositorPosition] = anchor.web3.PublicKey.findProgramAddressSync(
+      [Buffer.from("position"), hedgedVault.vault.toBuffer(), authority.toBuffer()],
+      program.programId
+    );
+    const shareAccount = getAssociatedTokenAddressSync(hedgedVault.shareMint, authority);
+    await program.methods
+      .deposit(new anchor.BN(10_000_000), new anchor.BN(0))
+      .accounts({
+        vault: hedgedVault.vault,
+        userPosition: depositorPosition,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: hedgedVault.vaultTokenAccount,
+        shareMint: hedgedVault.shareMint,
+        userShareAccount: shareAccount,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+
+    // The vault holds 4 units of a token priced at 2.5 asset units and hedges them in full
+    const holdingMint = await createMint(provider.connection, payer, authority, null, 9);
+    const holdingTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        holdingMint,
+        hedgedVault.vault,
+        true
+      )
+    ).address;
+    await mintTo(provider.connection, payer, holdingMint, holdingTokenAccount, payer, 4_000_000_000);
+    const priceFeed = anchor.web3.Keypair.generate();
+    await oracleProgram.methods
+      .initializePriceFeed(new anchor.BN(2_500_000), new anchor.BN(1_000), -6)
+      .accounts({ priceFeed: priceFeed.publicKey, authority: authority })
+      .signers([priceFeed])
+      .rpc();
+    await program.methods
+      .addHolding()
+      .accounts({
+        vault: hedgedVault.vault,
+        authority: authority,
+        holdingMint: holdingMint,
+        holdingTokenAccount: holdingTokenAccount,
+        oracle: priceFeed.publicKey,
+      })
+      .rpc();
+    const market = await createMarket(holdingMint, assetMint);
+    await program.methods
+      .setHedgeMarket(holdingMint)
+      .accounts({ vault: hedgedVault.vault, authority: authority, market })
+      .rpc();
+
+    const batchId = new anchor.BN(0);
+    const hedgeOrder = orderPdaFor(market, hedgedVault.vault, batchId);
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    await program.methods
+      .executeStrategy()
+      .accounts({
+        vault: hedgedVault.vault,
+        vaultTokenAccount: hedgedVault.vaultTokenAccount,
+        shareMint: hedgedVault.shareMint,
+        keeper: authority,
+        keeperShareAccount: shareAccount,
+        dexProgram: dexProgram.programId,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts([
+        { pubkey: holdingTokenAccount, isSigner: false, isWritable: false },
+        { pubkey: priceFeed.publicKey, isSigner: false, isWritable: false },
+        { pubkey: market, isSigner: false, isWritable: false },
+        { pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: hedgeOrder, isSigner: false, isWritable: true },
+      ])
+      .rpc();
+    let vaultAccount = await program.account.vault.fetch(hedgedVault.vault);
+    expect(vaultAccount.holdingsValue.toNumber()).to.equal(10_000_000);
+    expect(vaultAccount.hedgeOrders[0].amount.toNumber()).to.equal(4_000_000_000);
+
+    // A counterparty bids for half of the hedge at the oracle price
+    const bidder = await fundedKeypair();
+    const bidderBase = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, holdingMint, bidder.publicKey)
+    ).address;
+    const bidderQuote = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, assetMint, bidder.publicKey)
+    ).address;
+    await mintTo(provider.connection, payer, assetMint, bidderQuote, payer, 10_000_000);
+    const bid = orderPdaFor(market, bidder.publicKey, batchId);
+    await dexProgram.methods
+      .placeOrder(
+        { buy: {} },
+        new anchor.BN(2_500),
+        new anchor.BN(2_000_000_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 10)
+      )
+      .accounts({ market, order: bid, trader: bidder.publicKey, payer: bidder.publicKey })
+      .signers([bidder])
+      .rpc();
+
+    const settle = () =>
+      program.methods
+        .settleHedge(holdingMint, new anchor.BN(2_000_000_000), new anchor.BN(2_500))
+        .accounts({
+          vault: hedgedVault.vault,
+          vaultTokenAccount: hedgedVault.vaultTokenAccount,
+          holdingTokenAccount: holdingTokenAccount,
+          oracle: priceFeed.publicKey,
+          market,
+          hedgeOrder,
+          counterpartyOrder: bid,
+          counterparty: bidder.publicKey,
+          counterpartyBaseAccount: bidderBase,
+          counterpartyQuoteAccount: bidderQuote,
+          dexProgram: dexProgram.programId,
+        })
+        .signers([bidder])
+        .rpc({ commitment: "confirmed" });
+    const setPrice = (price: number) =>
+      oracleProgram.methods
+        .setPrice(new anchor.BN(price), new anchor.BN(1_000))
+        .accounts({ priceFeed: priceFeed.publicKey, authority: authority })
+        .rpc();
+
+    // Once the oracle moves up to 3, the order's old limit is too cheap to fill
+    await setPrice(3_000_000);
+    try {
+      await settle();
+      expect.fail("Should have thrown error for a fill below the oracle price");
+    } catch (error) {
+      expect(error.toString()).to.include("HedgePriceBelowOracle");
+    }
+    await setPrice(2_500_000);
+
+    const tx = await settle();
+    const settled = (await eventsIn(tx)).find((e) => e.name === "hedgeSettled");
+    expect(settled.data.amount.toNumber()).to.equal(2_000_000_000);
+    expect(settled.data.remaining.toNumber()).to.equal(2_000_000_000);
+
+    // Half the holding became cash, so both the long and the short halved
+    vaultAccount = await program.account.vault.fetch(hedgedVault.vault);
+    expect(vaultAccount.holdingsValue.toNumber()).to.equal(5_000_000);
+    expect(vaultAccount.hedgeOrders[0].amount.toNumber()).to.equal(2_000_000_000);
+    const liquid = await provider.connection.getTokenAccountBalance(hedgedVault.vaultTokenAccount);
+    expect(Number(liquid.value.amount)).to.equal(15_000_000);
+    const inventory = await provider.connection.getTokenAccountBalance(holdingTokenAccount);
+    expect(Number(inventory.value.amount)).to.equal(2_000_000_000);
+    const orderAccount = await dexProgram.account.order.fetch(hedgeOrder);
+    expect(orderAccount.status).to.deep.equal({ partiallyFilled: {} });
+
+    // The unfilled half can still fill, so the vault keeps tracking it
+    try {
+      await program.methods
+        .cancelHedgeOrder(holdingMint)
+        .accounts({ vault: hedgedVault.vault, authority: authority, order: hedgeOrder })
+        .rpc();
+      expect.fail("Should have thrown error for a partially filled hedge order");
+    } catch (error) {
+      expect(error.toString()).to.include("HedgeOrderPartiallyFilled");
+    }
+  });
+
+  it("Lets every depositor exit a shut-down vault with a holding and an open hedge", async () => {
+    const hedgedVault = await createVault(new anchor.BN(15), { deltaNeutral: {} }, 5);
+    const depositInto = async (user: anchor.web3.Keypair | null, tokenAccount: anchor.web3.PublicKey) => {
+      const userKey = user ? user.publicKey : authority;
+      const [position] = anchor.web3.PublicKey.findProgramAddressSync(
+        [Buffer.from("position"), hedgedVault.vault.toBuffer(), userKey.toBuffer()],
+        program.programId
+      );
+      const shareAccount = getAssociatedTokenAddressSync(hedgedVault.shareMint, userKey);
+      await program.methods
+        .deposit(new anchor.BN(10_000_000), new anchor.BN(0))
+        .accounts({
+          vault: hedgedVault.vault,
+          userPosition: position,
+          user: userKey,
+          userTokenAccount: tokenAccount,
+          vaultTokenAccount: hedgedVault.vaultTokenAccount,
+          shareMint: hedgedVault.shareMint,
+          userShareAccount: shareAccount,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .signers(user ? [user] : [])
+        .rpc();
+      const withdrawAll = async () => {
+        const shares = await provider.connection.getTokenAccountBalance(shareAccount);
+        await program.methods
+          .withdraw(new anchor.BN(shares.value.amount), new anchor.BN(0))
+          .accounts({
+            vault: hedgedVault.vault,
+            userPosition: position,
+            user: userKey,
+            userTokenAccount: tokenAccount,
+            vaultTokenAccount: hedgedVault.vaultTokenAccount,
+            shareMint: hedgedVault.shareMint,
+            userShareAccount: shareAccount,
+          })
+          .signers(user ? [user] : [])
+          .rpc();
+        return Number((await provider.connection.getTokenAccountBalance(shareAccount)).value.amount);
+      };
+      return { shareAccount, withdrawAll };
+    };
+
+    // Two depositors of 10 asset units each
+    const first = await depositInto(null, userTokenAccount);
+    const secondUser = await fundedKeypair();
+    const secondTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, assetMint, secondUser.publicKey)
+    ).address;
+    await mintTo(provider.connection, payer, assetMint, secondTokenAccount, payer, 10_000_000);
+    const second = await depositInto(secondUser, secondTokenAccount);
+
+    // The vault also holds 4 units of a token priced at 2.5 asset units, hedged in full
+    const holdingMint = await createMint(provider.connection, payer, authority, null, 9);
+    const holdingTokenAccount = (
+      await getOrCreateAssociatedTokenAccount(
+        provider.connection,
+        payer,
+        holdingMint,
+        hedgedVault.vault,
+        true
+      )
+    ).address;
+    await mintTo(provider.connection, payer, holdingMint, holdingTokenAccount, payer, 4_000_000_000);
+    const priceFeed = anchor.web3.Keypair.generate();
+    await oracleProgram.methods
+      .initializePriceFeed(new anchor.BN(2_500_000), new anchor.BN(1_000), -6)
+      .accounts({ priceFeed: priceFeed.publicKey, authority: authority })
+      .signers([priceFeed])
+      .rpc();
+    const refreshPrice = () =>
+      oracleProgram.methods
+        .setPrice(new anchor.BN(2_500_000), new anchor.BN(1_000))
+        .accounts({ priceFeed: priceFeed.publicKey, authority: authority })
+        .rpc();
+    await program.methods
+      .addHolding()
+      .accounts({
+        vault: hedgedVault.vault,
+        authority: authority,
+        holdingMint: holdingMint,
+        holdingTokenAccount: holdingTokenAccount,
+        oracle: priceFeed.publicKey,
+      })
+      .rpc();
+    const market = await createMarket(holdingMint, assetMint);
+    await program.methods
+      .setHedgeMarket(holdingMint)
+      .accounts({ vault: hedgedVault.vault, authority: authority, market })
+      .rpc();
+
+    const hedgeOrder = orderPdaFor(market, hedgedVault.vault, new anchor.BN(0));
+    await new Promise((resolve) => setTimeout(resolve, 2000));
+    await program.methods
+      .executeStrategy()
+      .accounts({
+        vault: hedgedVault.vault,
+        vaultTokenAccount: hedgedVault.vaultTokenAccount,
+        shareMint: hedgedVault.shareMint,
+        keeper: authority,
+        keeperShareAccount: first.shareAccount,
+        dexProgram: dexProgram.programId,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .remainingAccounts([
+        { pubkey: holdingTokenAccount, isSigner: false, isWritable: false },
+        { pubkey: priceFeed.publicKey, isSigner: false, isWritable: false },
+        { pubkey: market, isSigner: false, isWritable: false },
+        { pubkey: anchor.web3.SystemProgram.programId, isSigner: false, isWritable: false },
+        { pubkey: hedgeOrder, isSigner: false, isWritable: true },
+      ])
+      .rpc();
+
+    await refreshPrice();
+    await program.methods
+      .shutdownVault()
+      .accounts({
+        vault: hedgedVault.vault,
+        signer: authority,
+        vaultTokenAccount: hedgedVault.vaultTokenAccount,
+      })
+      .remainingAccounts([
+        { pubkey: holdingTokenAccount, isSigner: false, isWritable: false },
+        { pubkey: priceFeed.publicKey, isSigner: false, isWritable: false },
+      ])
+      .rpc();
+    let vaultAccount = await program.account.vault.fetch(hedgedVault.vault);
+    expect(vaultAccount.isShutdown).to.be.true;
+    expect(vaultAccount.holdingsValue.toNumber()).to.equal(10_000_000);
+    expect(vaultAccount.hedgeOrders.length).to.equal(1);
+
+    // Before the holding is sold, a withdrawal is paid pro-rata from the 20 liquid units:
+    // 10_000_000_000 * 20_000_001 / (20_000_000_000 + 1_000)
+    const firstBalanceBefore = await provider.connection.getTokenAccountBalance(userTokenAccount);
+    expect(await first.withdrawAll()).to.equal(0);
+    const firstBalanceAfter = await provider.connection.getTokenAccountBalance(userTokenAccount);
+    expect(Number(firstBalanceAfter.value.amount) - Number(firstBalanceBefore.value.amount)).to.equal(
+      10_000_000
+    );
+
+    // The hedge is cancelled and the holding offered at the oracle price in the next batch
+    await program.methods
+      .cancelHedgeOrder(holdingMint)
+      .accounts({ vault: hedgedVault.vault, authority: authority, order: hedgeOrder })
+      .rpc();
+    await dexProgram.methods.executeBatchAuction().accounts({ market }).rpc();
+    const batchId = new anchor.BN(1);
+    const unwindOrder = orderPdaFor(market, hedgedVault.vault, batchId);
+    await refreshPrice();
+    await program.methods
+      .unwindHolding(holdingMint)
+      .accounts({
+        vault: hedgedVault.vault,
+        signer: authority,
+        holdingTokenAccount: holdingTokenAccount,
+        oracle: priceFeed.publicKey,
+        market,
+        order: unwindOrder,
+        dexProgram: dexProgram.programId,
+        systemProgram: anchor.web3.SystemProgram.programId,
+      })
+      .rpc();
+    vaultAccount = await program.account.vault.fetch(hedgedVault.vault);
+    expect(vaultAccount.hedgeOrders[0].order.toString()).to.equal(unwindOrder.toString());
+    expect(vaultAccount.hedgeOrders[0].amount.toNumber()).to.equal(4_000_000_000);
+    expect(vaultAccount.hedgeOrders[0].price.toNumber()).to.equal(2_500);
+
+    // A counterparty buys the whole holding, still settling against the shut-down vault
+    const bidder = await fundedKeypair();
+    const bidderBase = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, holdingMint, bidder.publicKey)
+    ).address;
+    const bidderQuote = (
+      await getOrCreateAssociatedTokenAccount(provider.connection, payer, assetMint, bidder.publicKey)
+    ).address;
+    await mintTo(provider.connection, payer, assetMint, bidderQuote, payer, 10_000_000);
+    const bid = orderPdaFor(market, bidder.publicKey, batchId);
+    await dexProgram.methods
+      .placeOrder(
+        { buy: {} },
+        new anchor.BN(2_500),
+        new anchor.BN(4_000_000_000),
+        new anchor.BN(Math.floor(Date.now() / 1000) + 10)
+      )
+      .accounts({ market, order: bid, trader: bidder.publicKey, payer: bidder.publicKey })
+      .signers([bidder])
+      .rpc();
+    await program.methods
+      .settleHedge(holdingMint, new anchor.BN(4_000_000_000), new anchor.BN(2_500))
+      .accounts({
+        vault: hedgedVault.vault,
+        vaultTokenAccount: hedgedVault.vaultTokenAccount,
+        holdingTokenAccount: holdingTokenAccount,
+        oracle: priceFeed.publicKey,
+        market,
+        hedgeOrder: unwindOrder,
+        counterpartyOrder: bid,
+        counterparty: bidder.publicKey,
+        counterpartyBaseAccount: bidderBase,
+        counterpartyQuoteAccount: bidderQuote,
+        dexProgram: dexProgram.programId,
+      })
+      .signers([bidder])
+      .rpc();
+    vaultAccount = await program.account.vault.fetch(hedgedVault.vault);
+    expect(vaultAccount.holdingsValue.toNumber()).to.equal(0);
+    expect(vaultAccount.hedgeOrders.length).to.equal(0);
+
+    // The remaining depositor takes the rest of the liquid balance and the sale proceeds:
+    // 10_000_000_000 * 20_000_001 / (10_000_000_000 + 1_000), leaving 1 unit of rounding dust
+    expect(await second.withdrawAll()).to.equal(0);
+    const secondBalance = await provider.connection.getTokenAccountBalance(secondTokenAccount);
+    expect(Number(secondBalance.value.amount)).to.equal(19_999_999);
+    vaultAccount = await program.account.vault.fetch(hedgedVault.vault);
+    expect(vaultAccount.totalShares.toNumber()).to.equal(0);
+  });
+
+  // Shutdown is irreversible, so this must remain the last test against the vault
+  it("Shuts the vault down and allows only withdrawals", async () => {
+    await program.methods
+      .shutdownVault()
+      .accounts({
+        vault: vaultPda,
+        signer: authority,
+        vaultTokenAccount: vaultTokenAccountPda,
+      })
+      .rpc();
+
+    const vaultAccount = await program.account.vault.fetch(vaultPda);
+    expect(vaultAccount.isShutdown).to.be.true;
+    expect(vaultAccount.deployedAssets.toNumber()).to.equal(0);
+
+    try {
+      await program.methods
+        .deposit(new anchor.BN(1_000_000), new anchor.BN(0))
+        .accounts({
+          vault: vaultPda,
+          userPosition: userPositionPda,
+          user: authority,
+          userTokenAccount: userTokenAccount,
+          vaultTokenAccount: vaultTokenAccountPda,
+          shareMint: shareMintPda,
+          userShareAccount: userShareAccount,
+          systemProgram: anchor.web3.SystemProgram.programId,
+        })
+        .rpc();
+      expect.fail("Should have thrown error for deposit after shutdown");
+    } catch (error) {
+      expect(error.toString()).to.include("VaultIsShutdown");
+    }
+
+    const balance = await provider.connection.getTokenAccountBalance(userShareAccount);
+    await program.methods
+      .withdraw(new anchor.BN(balance.value.amount), new anchor.BN(0))
+      .accounts({
+        vault: vaultPda,
+        userPosition: userPositionPda,
+        user: authority,
+        userTokenAccount: userTokenAccount,
+        vaultTokenAccount: vaultTokenAccountPda,
+        shareMint: shareMintPda,
+        userShareAccount: userShareAccount,
+      })
+      .rpc();
+
+    const balanceAfter = await provider.connection.getTokenAccountBalance(userShareAccount);
+    expect(Number(balanceAfter.value.amount)).to.equal(0);
+  });
+});